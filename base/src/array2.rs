@@ -1,6 +1,9 @@
 use crate::extents::Extents;
 use crate::extents::Point;
+use crate::extents::Rect;
 
+use serde::de::Error as _;
+use serde::ser::SerializeStruct;
 use std::fmt::Display;
 use std::fmt::Formatter;
 use std::ops::Index;
@@ -13,6 +16,80 @@ pub struct Array2<T>
     size: Extents,
 }
 
+// which corner of the old contents stays in place when resizing, top is row 0
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Anchor
+{
+    #[default]
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+    Center,
+}
+
+// the 8 symmetries of a rectangle, rotations are clockwise with row 0 at the top
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Symmetry
+{
+    Identity,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    FlipHorizontal,
+    FlipVertical,
+    Transpose,
+    AntiTranspose,
+}
+
+impl Symmetry
+{
+    pub const ALL: [Symmetry; 8] = [
+        Symmetry::Identity,
+        Symmetry::Rotate90,
+        Symmetry::Rotate180,
+        Symmetry::Rotate270,
+        Symmetry::FlipHorizontal,
+        Symmetry::FlipVertical,
+        Symmetry::Transpose,
+        Symmetry::AntiTranspose,
+    ];
+
+    pub fn swaps_axes(&self) -> bool
+    {
+        matches!(self, Symmetry::Rotate90 | Symmetry::Rotate270 | Symmetry::Transpose | Symmetry::AntiTranspose)
+    }
+
+    pub fn transformed_size(&self, size: Extents) -> Extents
+    {
+        if self.swaps_axes()
+        {
+            (size.height, size.width).into()
+        }
+        else
+        {
+            size
+        }
+    }
+
+    // where a position of the transformed array comes from in an array of the given size
+    pub fn source_pos(&self, size: Extents, pos: Point) -> Point
+    {
+        let (w, h) = (size.width, size.height);
+        match self
+        {
+            Symmetry::Identity => pos,
+            Symmetry::Rotate90 => Point::new(pos.y, h - 1 - pos.x),
+            Symmetry::Rotate180 => Point::new(w - 1 - pos.x, h - 1 - pos.y),
+            Symmetry::Rotate270 => Point::new(w - 1 - pos.y, pos.x),
+            Symmetry::FlipHorizontal => Point::new(w - 1 - pos.x, pos.y),
+            Symmetry::FlipVertical => Point::new(pos.x, h - 1 - pos.y),
+            Symmetry::Transpose => Point::new(pos.y, pos.x),
+            Symmetry::AntiTranspose => Point::new(w - 1 - pos.y, h - 1 - pos.x),
+        }
+    }
+}
+
 #[derive(Debug, Eq, PartialEq)]
 pub enum Error
 {
@@ -232,6 +309,20 @@ impl<T> Array2<T>
         })
     }
 
+    pub fn iter_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut T>
+    {
+        self.array.iter_mut()
+    }
+
+    pub fn row_iter_mut(&mut self, y: i32) -> Result<impl DoubleEndedIterator<Item = &mut T>, Error>
+    {
+        let start = self
+            .get_index((0, y).into())
+            .ok_or(Error::IndicesOutOfBounds((0, y).into()))?;
+        let end = start + (self.size.width as usize);
+        Ok(self.array[start..end].iter_mut())
+    }
+
     pub fn index2_space(&self) -> impl DoubleEndedIterator<Item = Point> + Clone
     {
         self.size.index2_space()
@@ -243,6 +334,349 @@ impl<T> Array2<T>
     {
         self.index2_space().map(move |i| (i, &self[i]))
     }
+
+    pub fn enumerate_mut(
+        &mut self,
+    ) -> impl DoubleEndedIterator<Item = (Point, &mut T)>
+    {
+        let size = self.size;
+        self.array.iter_mut()
+            .enumerate()
+            .map(move |(i, e)| (size.get_index2(i).expect("Array2 -- enumerate_mut should never fail"), e))
+    }
+
+    pub fn rect(&self) -> Rect
+    {
+        self.size.into()
+    }
+
+    fn check_rect(&self, rect: Rect) -> Result<(), Error>
+    {
+        if !self.rect().contains_rect(&rect)
+        {
+            return Err(Error::IndicesOutOfBounds(rect.max()));
+        }
+        Ok(())
+    }
+
+    // borrows the region, indexed relative to its origin
+    pub fn view(&self, rect: Rect) -> Result<Array2View<'_, T>, Error>
+    {
+        self.check_rect(rect)?;
+        Ok(Array2View {
+            array: self,
+            origin: rect.origin,
+            size: rect.size,
+        })
+    }
+
+    pub fn view_mut(&mut self, rect: Rect) -> Result<Array2ViewMut<'_, T>, Error>
+    {
+        self.check_rect(rect)?;
+        Ok(Array2ViewMut {
+            array: self,
+            origin: rect.origin,
+            size: rect.size,
+        })
+    }
+
+    pub fn fill_rect(&mut self, rect: Rect, element: T) -> Result<(), Error>
+    where
+        T: Clone,
+    {
+        self.view_mut(rect)?.fill_with(element);
+        Ok(())
+    }
+
+    // copies all of src with its origin at dst_pos, clipped to self, returns the region written
+    pub fn blit(&mut self, src: &Array2<T>, dst_pos: Point) -> Option<Rect>
+    where
+        T: Clone,
+    {
+        let written = src.rect().translated(dst_pos).clamp_to(self.size)?;
+        for pos in written.points()
+        {
+            self[pos] = src[pos - dst_pos].clone();
+        }
+        Some(written)
+    }
+
+    // negative sizes are clamped to zero, leaving an empty array
+    pub fn resize(&mut self, new_size: Extents, fill: T, anchor: Anchor)
+    where
+        T: Clone,
+    {
+        let new_size = Extents{ width: new_size.width.max(0), height: new_size.height.max(0) };
+        let delta = Point::new(new_size.width - self.size.width, new_size.height - self.size.height);
+        let offset = match anchor
+        {
+            Anchor::TopLeft => Point::ZERO,
+            Anchor::TopRight => Point::new(delta.x, 0),
+            Anchor::BottomLeft => Point::new(0, delta.y),
+            Anchor::BottomRight => delta,
+            Anchor::Center => delta / 2,
+        };
+
+        let mut resized = Array2::filled_with(fill, new_size);
+        resized.blit(self, offset);
+        *self = resized;
+    }
+
+    // keeps only the region, which becomes the new origin
+    pub fn crop(&mut self, rect: Rect) -> Result<(), Error>
+    where
+        T: Clone,
+    {
+        *self = self.view(rect)?.to_array2();
+        Ok(())
+    }
+
+    pub fn transformed(&self, symmetry: Symmetry) -> Self
+    where
+        T: Clone,
+    {
+        let size = symmetry.transformed_size(self.size);
+        let array = size.index2_space()
+            .map(|pos| self[symmetry.source_pos(self.size, pos)].clone())
+            .collect();
+        Array2 {
+            array,
+            size,
+        }
+    }
+
+    pub fn rotated_90(&self) -> Self
+    where
+        T: Clone,
+    {
+        self.transformed(Symmetry::Rotate90)
+    }
+
+    pub fn rotated_180(&self) -> Self
+    where
+        T: Clone,
+    {
+        self.transformed(Symmetry::Rotate180)
+    }
+
+    pub fn rotated_270(&self) -> Self
+    where
+        T: Clone,
+    {
+        self.transformed(Symmetry::Rotate270)
+    }
+
+    pub fn flipped_horizontal(&self) -> Self
+    where
+        T: Clone,
+    {
+        self.transformed(Symmetry::FlipHorizontal)
+    }
+
+    pub fn flipped_vertical(&self) -> Self
+    where
+        T: Clone,
+    {
+        self.transformed(Symmetry::FlipVertical)
+    }
+
+    pub fn transposed(&self) -> Self
+    where
+        T: Clone,
+    {
+        self.transformed(Symmetry::Transpose)
+    }
+
+    // copies the region from src into the same region of self
+    pub fn copy_from(&mut self, src: &Array2<T>, rect: Rect) -> Result<(), Error>
+    where
+        T: Clone,
+    {
+        src.check_rect(rect)?;
+        self.check_rect(rect)?;
+        for pos in rect.points()
+        {
+            self[pos] = src[pos].clone();
+        }
+        Ok(())
+    }
+}
+
+// a borrowed rectangular region of an Array2
+#[derive(Debug, Clone, Copy)]
+pub struct Array2View<'a, T>
+{
+    array: &'a Array2<T>,
+    origin: Point,
+    size: Extents,
+}
+
+impl<'a, T> Array2View<'a, T>
+{
+    pub fn size(&self) -> Extents
+    {
+        self.size
+    }
+
+    pub fn rect(&self) -> Rect
+    {
+        Rect::new(self.origin, self.size)
+    }
+
+    pub fn get_by_index2(&self, pos: Point) -> Option<&'a T>
+    {
+        if !self.size.is_valid_pos(pos)
+        {
+            return None;
+        }
+        self.array.get_by_index2(self.origin + pos)
+    }
+
+    pub fn row_iter(&self, y: i32) -> Result<impl DoubleEndedIterator<Item = &'a T> + Clone, Error>
+    {
+        if y < 0 || y >= self.size.height
+        {
+            return Err(Error::IndicesOutOfBounds((0, y).into()));
+        }
+        let start = self.array.size.get_index(self.origin + Point::new(0, y)).unwrap_or(0);
+        let end = start + (self.size.width as usize);
+        Ok(self.array.array[start..end].iter())
+    }
+
+    pub fn index2_space(&self) -> impl DoubleEndedIterator<Item = Point> + Clone
+    {
+        self.size.index2_space()
+    }
+
+    pub fn enumerate(&self) -> impl DoubleEndedIterator<Item = (Point, &'a T)> + Clone
+    {
+        let array = self.array;
+        let origin = self.origin;
+        self.index2_space().map(move |i| (i, &array[origin + i]))
+    }
+
+    pub fn to_array2(&self) -> Array2<T>
+    where
+        T: Clone,
+    {
+        Array2::from_iter(self.enumerate().map(|(_, e)| e.clone()), self.size)
+            .expect("Array2View -- view is always in bounds")
+    }
+}
+
+impl<T> Index<Point> for Array2View<'_, T>
+{
+    type Output = T;
+
+    fn index(&self, pos: Point) -> &Self::Output
+    {
+        self.get_by_index2(pos)
+            .unwrap_or_else(|| panic!("Array2View -- Index indices {}, {} out of bounds", pos.x, pos.y))
+    }
+}
+
+// a mutably borrowed rectangular region of an Array2
+#[derive(Debug)]
+pub struct Array2ViewMut<'a, T>
+{
+    array: &'a mut Array2<T>,
+    origin: Point,
+    size: Extents,
+}
+
+impl<T> Array2ViewMut<'_, T>
+{
+    pub fn size(&self) -> Extents
+    {
+        self.size
+    }
+
+    pub fn rect(&self) -> Rect
+    {
+        Rect::new(self.origin, self.size)
+    }
+
+    pub fn get_by_index2(&self, pos: Point) -> Option<&T>
+    {
+        if !self.size.is_valid_pos(pos)
+        {
+            return None;
+        }
+        self.array.get_by_index2(self.origin + pos)
+    }
+
+    pub fn get_by_index2_mut(&mut self, pos: Point) -> Option<&mut T>
+    {
+        if !self.size.is_valid_pos(pos)
+        {
+            return None;
+        }
+        self.array.get_by_index2_mut(self.origin + pos)
+    }
+
+    pub fn row_iter_mut(&mut self, y: i32) -> Result<impl DoubleEndedIterator<Item = &mut T>, Error>
+    {
+        if y < 0 || y >= self.size.height
+        {
+            return Err(Error::IndicesOutOfBounds((0, y).into()));
+        }
+        let start = self.array.size.get_index(self.origin + Point::new(0, y)).unwrap_or(0);
+        let end = start + (self.size.width as usize);
+        Ok(self.array.array[start..end].iter_mut())
+    }
+
+    pub fn index2_space(&self) -> impl DoubleEndedIterator<Item = Point> + Clone
+    {
+        self.size.index2_space()
+    }
+
+    pub fn enumerate_mut(&mut self) -> impl Iterator<Item = (Point, &mut T)>
+    {
+        let origin = self.origin;
+        let size = self.size;
+        self.array.array
+            .chunks_mut(self.array.size.width.max(1) as usize)
+            .skip(origin.y as usize)
+            .take(size.height as usize)
+            .enumerate()
+            .flat_map(move |(y, row)|
+            {
+                row[origin.x as usize..(origin.x + size.width) as usize]
+                    .iter_mut()
+                    .enumerate()
+                    .map(move |(x, e)| (Point::new(x as i32, y as i32), e))
+            })
+    }
+
+    pub fn fill_with(&mut self, element: T)
+    where
+        T: Clone,
+    {
+        for (_, cell) in self.enumerate_mut()
+        {
+            *cell = element.clone();
+        }
+    }
+}
+
+impl<T> Index<Point> for Array2ViewMut<'_, T>
+{
+    type Output = T;
+
+    fn index(&self, pos: Point) -> &Self::Output
+    {
+        self.get_by_index2(pos)
+            .unwrap_or_else(|| panic!("Array2ViewMut -- Index indices {}, {} out of bounds", pos.x, pos.y))
+    }
+}
+
+impl<T> IndexMut<Point> for Array2ViewMut<'_, T>
+{
+    fn index_mut(&mut self, pos: Point) -> &mut Self::Output
+    {
+        self.get_by_index2_mut(pos)
+            .unwrap_or_else(|| panic!("Array2ViewMut -- Index mut indices {}, {} out of bounds", pos.x, pos.y))
+    }
 }
 
 impl<T> Index<Point> for Array2<T>
@@ -282,3 +716,170 @@ impl<T> IndexMut<usize> for Array2<T>
         &mut self.array[i]
     }
 }
+
+impl<T> serde::Serialize for Array2<T>
+where
+    T: serde::Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("Array2", 2)?;
+        state.serialize_field("size", &self.size)?;
+        state.serialize_field("array", &self.array)?;
+        state.end()
+    }
+}
+
+// mirrors the serialized layout, validated before it becomes an Array2
+#[derive(serde::Deserialize)]
+#[serde(rename = "Array2")]
+struct Array2Data<T>
+{
+    size: Extents,
+    array: Vec<T>,
+}
+
+impl<'de, T> serde::Deserialize<'de> for Array2<T>
+where
+    T: serde::Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let data = Array2Data::<T>::deserialize(deserializer)?;
+        if data.size.try_num_elements() != Some(data.array.len())
+        {
+            return Err(D::Error::custom(Error::DimensionMismatch));
+        }
+        Ok(Array2 {
+            array: data.array,
+            size: data.size,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn sample() -> Array2<u8>
+    {
+        Array2::from_iter(0.., Extents::new(3, 2)).unwrap()
+    }
+
+    #[test]
+    fn test_ron_round_trip()
+    {
+        let array = sample();
+        let text = ron::ser::to_string(&array).unwrap();
+        let read: Array2<u8> = ron::de::from_str(&text).unwrap();
+        assert_eq!(array, read);
+    }
+
+    #[test]
+    fn test_deserialize_bad_size()
+    {
+        assert!(ron::de::from_str::<Array2<u8>>("(size: (width: 65536, height: 65536), array: [])").is_err());
+        assert!(ron::de::from_str::<Array2<u8>>("(size: (width: 2147483647, height: 2147483647), array: [1])").is_err());
+        assert!(ron::de::from_str::<Array2<u8>>("(size: (width: -1, height: -1), array: [1])").is_err());
+        assert!(ron::de::from_str::<Array2<u8>>("(size: (width: 2, height: 1), array: [1])").is_err());
+    }
+
+    #[test]
+    fn test_binary_round_trip()
+    {
+        let array = sample();
+        let bytes = postcard::to_allocvec(&array).unwrap();
+        let read: Array2<u8> = postcard::from_bytes(&bytes).unwrap();
+        assert_eq!(array, read);
+    }
+
+    #[test]
+    fn test_views()
+    {
+        let mut array = Array2::<u8>::from_size(Extents::new(4, 3));
+        for (pos, cell) in array.enumerate_mut()
+        {
+            *cell = (pos.y * 4 + pos.x) as u8;
+        }
+
+        let view = array.view(Rect::new(Point::new(1, 1), Extents::new(2, 2))).unwrap();
+        assert_eq!(view[Point::new(0, 0)], 5);
+        assert_eq!(view.row_iter(1).unwrap().copied().collect::<Vec<_>>(), vec![9, 10]);
+        assert_eq!(view.get_by_index2(Point::new(2, 0)), None);
+        assert!(array.view(Rect::new(Point::new(3, 0), Extents::new(2, 1))).is_err());
+
+        let mut view = array.view_mut(Rect::new(Point::new(2, 0), Extents::new(2, 3))).unwrap();
+        view.fill_with(0);
+        view[Point::new(1, 2)] = 42;
+        assert_eq!(array.row_iter(0).unwrap().copied().collect::<Vec<_>>(), vec![0, 1, 0, 0]);
+        assert_eq!(array[Point::new(3, 2)], 42);
+
+        for cell in array.row_iter_mut(1).unwrap()
+        {
+            *cell = 7;
+        }
+        assert!(array.row_iter(1).unwrap().all(|cell| *cell == 7));
+
+        let mut other = Array2::<u8>::from_size(array.size());
+        let rect = Rect::new(Point::new(1, 1), Extents::new(3, 2));
+        other.copy_from(&array, rect).unwrap();
+        assert_eq!(other[Point::new(3, 2)], 42);
+        assert_eq!(other[Point::new(0, 1)], 0);
+        other.fill_rect(rect, 1).unwrap();
+        assert_eq!(other.raw_iter().filter(|cell| **cell == 1).count(), 6);
+        assert!(other.fill_rect(Rect::new(Point::new(-1, 0), Extents::new(1, 1)), 1).is_err());
+    }
+
+    #[test]
+    fn test_resize_crop_blit()
+    {
+        let mut array = sample();
+        array.resize(Extents::new(4, 3), 9, Anchor::BottomRight);
+        assert_eq!(array.row_iter(0).unwrap().copied().collect::<Vec<_>>(), vec![9, 9, 9, 9]);
+        assert_eq!(array.row_iter(2).unwrap().copied().collect::<Vec<_>>(), vec![9, 3, 4, 5]);
+
+        array.crop(Rect::new(Point::new(1, 1), Extents::new(3, 2))).unwrap();
+        assert_eq!(array, sample());
+
+        let mut target = Array2::<u8>::filled_with(0, Extents::new(2, 2));
+        let written = target.blit(&sample(), Point::new(1, -1));
+        assert_eq!(written, Some(Rect::new(Point::new(1, 0), Extents::new(1, 1))));
+        assert_eq!(target.raw_iter().copied().collect::<Vec<_>>(), vec![0, 3, 0, 0]);
+        assert_eq!(target.blit(&sample(), Point::new(2, 0)), None);
+
+        let mut array = sample();
+        array.resize(Extents{ width: -2, height: 3 }, 9, Anchor::Center);
+        assert_eq!(array.size(), Extents{ width: 0, height: 3 });
+        assert_eq!(array.raw_iter().count(), 0);
+    }
+
+    #[test]
+    fn test_transforms()
+    {
+        // 0 1 2
+        // 3 4 5
+        let array = sample();
+        let rows = |array: &Array2<u8>| array.rows_iter().map(|row| row.copied().collect::<Vec<_>>()).collect::<Vec<_>>();
+        assert_eq!(rows(&array.rotated_90()), vec![vec![3, 0], vec![4, 1], vec![5, 2]]);
+        assert_eq!(rows(&array.rotated_180()), vec![vec![5, 4, 3], vec![2, 1, 0]]);
+        assert_eq!(rows(&array.rotated_270()), vec![vec![2, 5], vec![1, 4], vec![0, 3]]);
+        assert_eq!(rows(&array.flipped_horizontal()), vec![vec![2, 1, 0], vec![5, 4, 3]]);
+        assert_eq!(rows(&array.flipped_vertical()), vec![vec![3, 4, 5], vec![0, 1, 2]]);
+        assert_eq!(rows(&array.transposed()), vec![vec![0, 3], vec![1, 4], vec![2, 5]]);
+        assert_eq!(rows(&array.transformed(Symmetry::AntiTranspose)), vec![vec![5, 2], vec![4, 1], vec![3, 0]]);
+        assert_eq!(array.rotated_90().rotated_270(), array);
+        assert_eq!(array.rotated_90().size(), Extents::new(2, 3));
+    }
+
+    #[test]
+    fn test_dimension_mismatch()
+    {
+        let err = ron::de::from_str::<Array2<u8>>("(size: (width: 2, height: 2), array: [0, 1, 2])").unwrap_err();
+        assert_eq!(err.code, ron::Error::Message(Error::DimensionMismatch.to_string()));
+    }
+}
@@ -1,2 +1,52 @@
 
 pub const ASSETS_FOLDER: &str = "assets";
+
+pub const ASSET_ROOT_ENV: &str = "BEVY_ASSET_ROOT";
+
+// the folder holding ASSETS_FOLDER, looked up in the same order as bevy's FileAssetReader::get_base_path so
+// files read here are the ones the AssetServer loads: BEVY_ASSET_ROOT, CARGO_MANIFEST_DIR, the executable's folder
+pub fn asset_root() -> std::path::PathBuf
+{
+    if let Ok(root) = std::env::var(ASSET_ROOT_ENV)
+    {
+        std::path::PathBuf::from(root)
+    }
+    else if let Ok(manifest_dir) = std::env::var("CARGO_MANIFEST_DIR")
+    {
+        std::path::PathBuf::from(manifest_dir)
+    }
+    else
+    {
+        std::env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(std::path::Path::to_path_buf))
+            .unwrap_or_default()
+    }
+}
+
+pub fn assets_folder() -> std::path::PathBuf
+{
+    asset_root().join(ASSETS_FOLDER)
+}
+
+pub fn asset_exists(path: &std::path::Path) -> bool
+{
+    assets_folder().join(path).is_file()
+}
+
+pub const APP_NAME: &str = "cim";
+
+// the per-user config folder of the platform, None when the environment doesn't tell where it is
+pub fn user_config_dir() -> Option<std::path::PathBuf>
+{
+    let var = |name: &str| std::env::var_os(name).filter(|value| !value.is_empty()).map(std::path::PathBuf::from);
+
+    #[cfg(target_os = "windows")]
+    let config = var("APPDATA");
+    #[cfg(target_os = "macos")]
+    let config = var("HOME").map(|home| home.join("Library/Application Support"));
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let config = var("XDG_CONFIG_HOME").or_else(|| var("HOME").map(|home| home.join(".config")));
+
+    config.map(|config| config.join(APP_NAME))
+}
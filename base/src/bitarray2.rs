@@ -0,0 +1,470 @@
+use crate::array2::Array2;
+use crate::array2::Error;
+use crate::extents::Extents;
+use crate::extents::Neighbours;
+use crate::extents::Point;
+
+use std::ops::BitAnd;
+use std::ops::BitAndAssign;
+use std::ops::BitOr;
+use std::ops::BitOrAssign;
+use std::ops::BitXor;
+use std::ops::BitXorAssign;
+use std::ops::Index;
+use std::ops::Not;
+
+type Word = u64;
+const WORD_BITS: i32 = Word::BITS as i32;
+
+// 2 dimensional array of bits, row major, each row starts on a new word.
+// bits past the width of a row are always zero, so whole words can be counted and combined.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct BitArray2
+{
+    words: Vec<Word>,
+    words_per_row: usize,
+    size: Extents,
+}
+
+impl BitArray2
+{
+    pub fn new(width: i32, height: i32) -> Self
+    {
+        Self::from_size((width, height).into())
+    }
+
+    pub fn from_size(size: Extents) -> Self
+    {
+        let words_per_row = ((size.width + WORD_BITS - 1) / WORD_BITS) as usize;
+        BitArray2 {
+            words: vec![0; words_per_row * size.height as usize],
+            words_per_row,
+            size,
+        }
+    }
+
+    pub fn filled_with(element: bool, size: Extents) -> Self
+    {
+        let mut array = Self::from_size(size);
+        array.fill_with(element);
+        array
+    }
+
+    pub fn from_array2<T, F>(array: &Array2<T>, mut predicate: F) -> Self
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let mut bits = Self::from_size(array.size());
+        for (pos, element) in array.enumerate()
+        {
+            if predicate(element)
+            {
+                bits.set_bit(pos, true);
+            }
+        }
+        bits
+    }
+
+    pub fn to_array2(&self) -> Array2<bool>
+    {
+        Array2::from_iter(self.index2_space().map(|pos| self.bit(pos)), self.size)
+            .expect("BitArray2 -- to_array2 should never fail")
+    }
+
+    pub fn height(&self) -> i32
+    {
+        self.size.height
+    }
+
+    pub fn width(&self) -> i32
+    {
+        self.size.width
+    }
+
+    pub fn size(&self) -> Extents
+    {
+        self.size
+    }
+
+    fn row_mask(&self, word: usize) -> Word
+    {
+        let bits = self.size.width - word as i32 * WORD_BITS;
+        if bits >= WORD_BITS
+        {
+            Word::MAX
+        }
+        else
+        {
+            (1 << bits) - 1
+        }
+    }
+
+    // clears the padding after the last bit of every row
+    fn mask_padding(&mut self)
+    {
+        if self.size.width % WORD_BITS == 0
+        {
+            return;
+        }
+        let last = self.words_per_row - 1;
+        let mask = self.row_mask(last);
+        for row in self.words.chunks_mut(self.words_per_row)
+        {
+            row[last] &= mask;
+        }
+    }
+
+    fn locate(&self, pos: Point) -> (usize, Word)
+    {
+        let word = pos.y as usize * self.words_per_row + (pos.x / WORD_BITS) as usize;
+        (word, 1 << (pos.x % WORD_BITS))
+    }
+
+    fn bit(&self, pos: Point) -> bool
+    {
+        let (word, mask) = self.locate(pos);
+        self.words[word] & mask != 0
+    }
+
+    fn set_bit(&mut self, pos: Point, element: bool)
+    {
+        let (word, mask) = self.locate(pos);
+        if element
+        {
+            self.words[word] |= mask;
+        }
+        else
+        {
+            self.words[word] &= !mask;
+        }
+    }
+
+    pub fn get_index(&self, pos: Point) -> Option<usize>
+    {
+        self.size.get_index(pos)
+    }
+
+    pub fn get_index2(&self, index: usize) -> Option<Point>
+    {
+        self.size.get_index2(index)
+    }
+
+    pub fn get_by_index2(&self, pos: Point) -> Option<bool>
+    {
+        self.size.is_valid_pos(pos).then(|| self.bit(pos))
+    }
+
+    pub fn get_by_index(&self, index: usize) -> Option<bool>
+    {
+        self.get_index2(index).map(|pos| self.bit(pos))
+    }
+
+    pub fn set_by_index2(&mut self, pos: Point, element: bool) -> Result<(), Error>
+    {
+        if !self.size.is_valid_pos(pos)
+        {
+            return Err(Error::IndicesOutOfBounds(pos));
+        }
+        self.set_bit(pos, element);
+        Ok(())
+    }
+
+    pub fn set_by_index(&mut self, index: usize, element: bool) -> Result<(), Error>
+    {
+        let pos = self.get_index2(index).ok_or(Error::IndexOutOfBounds(index))?;
+        self.set_bit(pos, element);
+        Ok(())
+    }
+
+    pub fn fill_with(&mut self, element: bool)
+    {
+        let word = if element { Word::MAX } else { 0 };
+        self.words.fill(word);
+        self.mask_padding();
+    }
+
+    pub fn index2_space(&self) -> impl DoubleEndedIterator<Item = Point> + Clone
+    {
+        self.size.index2_space()
+    }
+
+    pub fn enumerate(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = (Point, bool)> + Clone + '_
+    {
+        self.index2_space().map(move |pos| (pos, self.bit(pos)))
+    }
+
+    // positions of the set bits, skipping empty words
+    pub fn ones(&self) -> impl Iterator<Item = Point> + '_
+    {
+        self.words.iter().enumerate().flat_map(move |(i, word)|
+        {
+            let y = (i / self.words_per_row) as i32;
+            let x0 = (i % self.words_per_row) as i32 * WORD_BITS;
+            let mut word = *word;
+            std::iter::from_fn(move ||
+            {
+                if word == 0
+                {
+                    return None;
+                }
+                let bit = word.trailing_zeros() as i32;
+                word &= word - 1;
+                Some(Point::new(x0 + bit, y))
+            })
+        })
+    }
+
+    pub fn count_ones(&self) -> usize
+    {
+        self.words.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    pub fn any(&self) -> bool
+    {
+        self.words.iter().any(|word| *word != 0)
+    }
+
+    // moves every bit by offset, bits moved outside are lost and the vacated cells are cleared
+    pub fn shifted(&self, offset: Point) -> Self
+    {
+        let mut result = Self::from_size(self.size);
+        let row_words = self.words_per_row as i32;
+        let word_shift = offset.x.div_euclid(WORD_BITS);
+        let bit_shift = offset.x.rem_euclid(WORD_BITS) as u32;
+
+        for y in 0..self.size.height
+        {
+            let src_y = y - offset.y;
+            if src_y < 0 || src_y >= self.size.height
+            {
+                continue;
+            }
+            let src = &self.words[src_y as usize * self.words_per_row..][..self.words_per_row];
+            let dst = &mut result.words[y as usize * self.words_per_row..][..self.words_per_row];
+            let word_at = |i: i32| -> Word
+            {
+                if i < 0 || i >= row_words { 0 } else { src[i as usize] }
+            };
+
+            for (i, word) in dst.iter_mut().enumerate()
+            {
+                let i = i as i32 - word_shift;
+                *word = if bit_shift == 0
+                {
+                    word_at(i)
+                }
+                else
+                {
+                    (word_at(i) << bit_shift) | (word_at(i - 1) >> (Word::BITS - bit_shift))
+                };
+            }
+        }
+        result.mask_padding();
+        result
+    }
+
+    // number of set neighbours of every cell, summed with word wide bit sliced adders
+    pub fn neighbour_counts(&self, neighbours: Neighbours) -> Array2<u8>
+    {
+        let offsets = [
+            (Neighbours::TopLeft, Point::new(1, 1)),
+            (Neighbours::Top, Point::new(0, 1)),
+            (Neighbours::TopRight, Point::new(-1, 1)),
+            (Neighbours::Left, Point::new(1, 0)),
+            (Neighbours::Right, Point::new(-1, 0)),
+            (Neighbours::BottomLeft, Point::new(1, -1)),
+            (Neighbours::Bottom, Point::new(0, -1)),
+            (Neighbours::BottomRight, Point::new(-1, -1)),
+        ];
+
+        // 4 bits per cell are enough to count up to 8 neighbours
+        let mut planes: [BitArray2; 4] = std::array::from_fn(|_| Self::from_size(self.size));
+        for (neighbour, offset) in offsets
+        {
+            if !neighbours.contains(neighbour)
+            {
+                continue;
+            }
+            let mut carry = self.shifted(offset);
+            for plane in &mut planes
+            {
+                let next_carry = &*plane & &carry;
+                *plane ^= &carry;
+                carry = next_carry;
+            }
+        }
+
+        let mut counts = Array2::<u8>::from_size(self.size);
+        for (bit, plane) in planes.iter().enumerate()
+        {
+            for pos in plane.ones()
+            {
+                counts[pos] |= 1 << bit;
+            }
+        }
+        counts
+    }
+
+    fn zip_words(&mut self, other: &BitArray2, op: impl Fn(&mut Word, Word))
+    {
+        assert_eq!(self.size, other.size, "BitArray2 -- dimension mismatch");
+        for (word, other) in self.words.iter_mut().zip(other.words.iter())
+        {
+            op(word, *other);
+        }
+    }
+}
+
+impl Index<Point> for BitArray2
+{
+    type Output = bool;
+
+    fn index(&self, pos: Point) -> &Self::Output
+    {
+        match self.get_by_index2(pos)
+        {
+            Some(true) => &true,
+            Some(false) => &false,
+            None => panic!("BitArray2 -- Index indices {}, {} out of bounds", pos.x, pos.y),
+        }
+    }
+}
+
+impl BitAndAssign<&BitArray2> for BitArray2
+{
+    fn bitand_assign(&mut self, other: &BitArray2)
+    {
+        self.zip_words(other, |word, other| *word &= other);
+    }
+}
+
+impl BitOrAssign<&BitArray2> for BitArray2
+{
+    fn bitor_assign(&mut self, other: &BitArray2)
+    {
+        self.zip_words(other, |word, other| *word |= other);
+    }
+}
+
+impl BitXorAssign<&BitArray2> for BitArray2
+{
+    fn bitxor_assign(&mut self, other: &BitArray2)
+    {
+        self.zip_words(other, |word, other| *word ^= other);
+    }
+}
+
+impl BitAnd for &BitArray2
+{
+    type Output = BitArray2;
+
+    fn bitand(self, other: &BitArray2) -> BitArray2
+    {
+        let mut result = self.clone();
+        result &= other;
+        result
+    }
+}
+
+impl BitOr for &BitArray2
+{
+    type Output = BitArray2;
+
+    fn bitor(self, other: &BitArray2) -> BitArray2
+    {
+        let mut result = self.clone();
+        result |= other;
+        result
+    }
+}
+
+impl BitXor for &BitArray2
+{
+    type Output = BitArray2;
+
+    fn bitxor(self, other: &BitArray2) -> BitArray2
+    {
+        let mut result = self.clone();
+        result ^= other;
+        result
+    }
+}
+
+impl Not for &BitArray2
+{
+    type Output = BitArray2;
+
+    fn not(self) -> BitArray2
+    {
+        let mut result = self.clone();
+        for word in &mut result.words
+        {
+            *word = !*word;
+        }
+        result.mask_padding();
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn test_indexing_and_ops()
+    {
+        let size = Extents::new(70, 2);
+        let mut a = BitArray2::from_size(size);
+        a.set_by_index2(Point::new(69, 1), true).unwrap();
+        a.set_by_index2(Point::new(0, 0), true).unwrap();
+        assert!(a[Point::new(69, 1)]);
+        assert_eq!(a.get_by_index(a.get_index(Point::new(69, 1)).unwrap()), Some(true));
+        assert_eq!(a.get_by_index2(Point::new(70, 0)), None);
+        assert!(a.set_by_index2(Point::new(0, 2), true).is_err());
+
+        let b = BitArray2::filled_with(true, size);
+        assert_eq!(b.count_ones(), 140);
+        assert_eq!((&a & &b).count_ones(), 2);
+        assert_eq!((&a ^ &b).count_ones(), 138);
+        assert_eq!((!&a).count_ones(), 138);
+        assert_eq!((&a | &BitArray2::from_size(size)), a);
+        assert_eq!(a.ones().collect::<Vec<_>>(), vec![Point::new(0, 0), Point::new(69, 1)]);
+    }
+
+    #[test]
+    fn test_shifted()
+    {
+        let mut a = BitArray2::new(130, 3);
+        a.set_by_index2(Point::new(63, 0), true).unwrap();
+        a.set_by_index2(Point::new(129, 1), true).unwrap();
+
+        let shifted = a.shifted(Point::new(1, 1));
+        assert_eq!(shifted.ones().collect::<Vec<_>>(), vec![Point::new(64, 1)]);
+
+        let shifted = a.shifted(Point::new(-65, 0));
+        assert_eq!(shifted.ones().collect::<Vec<_>>(), vec![Point::new(64, 1)]);
+    }
+
+    #[test]
+    fn test_neighbour_counts()
+    {
+        let size = Extents::new(67, 5);
+        let mut mines = BitArray2::from_size(size);
+        for pos in [(0, 0), (1, 1), (63, 2), (64, 2), (66, 4), (65, 3)]
+        {
+            mines.set_by_index2(pos.into(), true).unwrap();
+        }
+
+        let counts = mines.neighbour_counts(Neighbours::All);
+        for pos in size.index2_space()
+        {
+            let expected = size.neighbours::<{ Neighbours::All.bits() }>(pos)
+                .filter(|n| mines[*n])
+                .count() as u8;
+            assert_eq!(counts[pos], expected, "at {pos}");
+        }
+    }
+}
@@ -5,10 +5,10 @@ use bitflags::bitflags;
 bitflags!
 {
     #[repr(transparent)]
-    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Deserialize, serde::Serialize)]
     pub struct Neighbours: u8
     {
-        const None = 0 << 0;
+        const None = 0;
         const Top = 1 << 0;
         const Bottom = 1 << 1;
         const Left = 1 << 2;
@@ -28,7 +28,7 @@ bitflags!
 
 pub type Point = glam::IVec2;
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, serde::Deserialize, serde::Serialize)]
 pub struct Extents
 {
     pub width: i32,
@@ -46,12 +46,20 @@ impl Extents
 
     pub fn num_elements(&self) -> usize
     {
-        (self.width * self.height) as usize
+        self.try_num_elements().expect("Extents -- negative or overflowing size")
+    }
+
+    // None for negative sizes and counts that don't fit in usize, for sizes read from untrusted data
+    pub fn try_num_elements(&self) -> Option<usize>
+    {
+        let width = usize::try_from(self.width).ok()?;
+        let height = usize::try_from(self.height).ok()?;
+        width.checked_mul(height)
     }
 
     pub fn is_valid_pos(&self, pos: Point) -> bool
     {
-        return pos.y >= 0 && pos.y < self.height && pos.x >= 0 && pos.x < self.width;
+        pos.y >= 0 && pos.y < self.height && pos.x >= 0 && pos.x < self.width
     }
 
     pub fn get_index(&self, pos: Point) -> Option<usize>
@@ -96,12 +104,22 @@ impl Extents
         &self,
         pos: Point,
     ) -> impl DoubleEndedIterator<Item = Point> + Clone
+    {
+        self.neighbours_masked(pos, Neighbours::from_bits_retain(FLAGS))
+    }
+
+    // same as neighbours, for masks only known at runtime
+    pub fn neighbours_masked(
+        &self,
+        pos: Point,
+        mask: Neighbours,
+    ) -> impl DoubleEndedIterator<Item = Point> + Clone
     {
         let mut neigh = ArrayVec::<Point, 8>::new();
 
         let can_add = |neighbour_position: Neighbours| -> bool
         {
-            Neighbours::from_bits_retain( FLAGS ) & neighbour_position != Neighbours::None
+            mask & neighbour_position != Neighbours::None
         };
 
         let mut try_add = |pos: Point|
@@ -125,6 +143,79 @@ impl Extents
 
         neigh.into_iter()
     }
+
+    // the cells at each offset of the stencil from pos that are inside the extents
+    pub fn stencil_neighbours<'a>(
+        &self,
+        pos: Point,
+        stencil: &'a Stencil,
+    ) -> impl DoubleEndedIterator<Item = Point> + Clone + 'a
+    {
+        let size = *self;
+        stencil.offsets()
+            .iter()
+            .map(move |offset| pos.wrapping_add(*offset))
+            .filter(move |pos| size.is_valid_pos(*pos))
+    }
+}
+
+// an arbitrary set of offsets around a cell, for neighbourhoods the Neighbours mask can't express
+#[derive(Debug, Clone, Eq, PartialEq, Hash, serde::Deserialize, serde::Serialize)]
+pub struct Stencil
+{
+    offsets: Vec<Point>,
+}
+
+impl Stencil
+{
+    pub fn new(offsets: Vec<Point>) -> Self
+    {
+        Stencil{ offsets }
+    }
+
+    // same cells, in the same order, as Extents::neighbours_masked
+    pub fn from_neighbours(mask: Neighbours) -> Self
+    {
+        let offsets = [
+            (Neighbours::TopLeft, (-1, -1)),
+            (Neighbours::Top, (0, -1)),
+            (Neighbours::TopRight, (1, -1)),
+            (Neighbours::Left, (-1, 0)),
+            (Neighbours::Right, (1, 0)),
+            (Neighbours::BottomLeft, (-1, 1)),
+            (Neighbours::Bottom, (0, 1)),
+            (Neighbours::BottomRight, (1, 1)),
+        ];
+        Stencil {
+            offsets: offsets.into_iter()
+                .filter(|(neighbour, _)| mask.contains(*neighbour))
+                .map(|(_, offset)| offset.into())
+                .collect(),
+        }
+    }
+
+    // every cell within radius steps in any direction (a square), row major, excluding the centre
+    pub fn radius(radius: i32) -> Self
+    {
+        let offsets = (-radius..=radius)
+            .flat_map(|y| (-radius..=radius).map(move |x| Point::new(x, y)))
+            .filter(|offset| *offset != Point::ZERO)
+            .collect();
+        Stencil{ offsets }
+    }
+
+    pub fn knight() -> Self
+    {
+        let offsets = [(-1, -2), (1, -2), (-2, -1), (2, -1), (-2, 1), (2, 1), (-1, 2), (1, 2)];
+        Stencil {
+            offsets: offsets.into_iter().map(Point::from).collect(),
+        }
+    }
+
+    pub fn offsets(&self) -> &[Point]
+    {
+        &self.offsets
+    }
 }
 
 impl From<(i32, i32)> for Extents
@@ -138,6 +229,109 @@ impl From<(i32, i32)> for Extents
     }
 }
 
+// a region with an origin, min inclusive, max exclusive
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, serde::Deserialize, serde::Serialize)]
+pub struct Rect
+{
+    pub origin: Point,
+    pub size: Extents,
+}
+
+impl Rect
+{
+    pub fn new(origin: Point, size: Extents) -> Self
+    {
+        assert!(size.width >= 0 && size.height >= 0);
+        Rect{ origin, size }
+    }
+
+    pub fn from_min_max(min: Point, max: Point) -> Self
+    {
+        let size = (max - min).max(Point::ZERO);
+        Rect{ origin: min, size: (size.x, size.y).into() }
+    }
+
+    pub fn min(&self) -> Point
+    {
+        self.origin
+    }
+
+    pub fn max(&self) -> Point
+    {
+        self.origin + Point::new(self.size.width, self.size.height)
+    }
+
+    pub fn is_empty(&self) -> bool
+    {
+        self.size.width <= 0 || self.size.height <= 0
+    }
+
+    pub fn contains(&self, pos: Point) -> bool
+    {
+        self.size.is_valid_pos(pos - self.origin)
+    }
+
+    pub fn contains_rect(&self, other: &Rect) -> bool
+    {
+        other.is_empty() || (other.min().cmpge(self.min()).all() && other.max().cmple(self.max()).all())
+    }
+
+    pub fn intersection(&self, other: &Rect) -> Option<Rect>
+    {
+        let rect = Rect::from_min_max(self.min().max(other.min()), self.max().min(other.max()));
+        if rect.is_empty()
+        {
+            None
+        }
+        else
+        {
+            Some(rect)
+        }
+    }
+
+    // the smallest rect covering both
+    pub fn union(&self, other: &Rect) -> Rect
+    {
+        if self.is_empty()
+        {
+            return *other;
+        }
+        if other.is_empty()
+        {
+            return *self;
+        }
+        Rect::from_min_max(self.min().min(other.min()), self.max().max(other.max()))
+    }
+
+    pub fn clamp_to(&self, extents: Extents) -> Option<Rect>
+    {
+        self.intersection(&extents.into())
+    }
+
+    pub fn translated(&self, offset: Point) -> Rect
+    {
+        Rect{ origin: self.origin + offset, size: self.size }
+    }
+
+    pub fn points(
+        self,
+    ) -> impl DoubleEndedIterator<Item = Point> + Clone
+    {
+        self.size.index2_space().map(move |pos| pos + self.origin)
+    }
+}
+
+impl From<Extents> for Rect
+{
+    fn from(size: Extents) -> Self
+    {
+        Self {
+            origin: Point::ZERO,
+            size,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests
 {
@@ -161,8 +355,8 @@ mod tests
     {
         let size = Extents::new( 2, 2 );
         check_iterators(
-            size.positions_row_major(),
-            [(0_i32, 0_i32), (1, 0), (0, 1), (1, 1)].into_iter()
+            size.index2_space(),
+            [(0_i32, 0_i32), (1, 0), (0, 1), (1, 1)].into_iter().map(Point::from)
         );
     }
 
@@ -171,17 +365,103 @@ mod tests
     {
         let size = Extents::new( 3, 3 );
         check_iterators(
-            size.neighbours::<{ Neighbours::All.bits() }>(1, 1),
+            size.neighbours::<{ Neighbours::All.bits() }>(Point::new(1, 1)),
             [
                 (0, 0), (1, 0), (2, 0), (0, 1), (2, 1), (0, 2), (1, 2), (2, 2),
-            ].into_iter()
+            ].into_iter().map(Point::from)
         );
 
         check_iterators(
-            size.neighbours::<{ Neighbours::Flush.bits() }>(1, 1),
+            size.neighbours::<{ Neighbours::Flush.bits() }>(Point::new(1, 1)),
             [
                 (1, 0), (0, 1), (2, 1), (1, 2)
-            ].into_iter()
+            ].into_iter().map(Point::from)
+        );
+    }
+
+    #[test]
+    fn test_rect()
+    {
+        let a = Rect::new(Point::new(0, 0), Extents::new(3, 3));
+        let b = Rect::new(Point::new(2, 1), Extents::new(3, 1));
+        assert_eq!(a.intersection(&b), Some(Rect::new(Point::new(2, 1), Extents::new(1, 1))));
+        assert_eq!(a.union(&b), Rect::new(Point::new(0, 0), Extents::new(5, 3)));
+        assert_eq!(b.clamp_to(Extents::new(4, 4)), Some(Rect::new(Point::new(2, 1), Extents::new(2, 1))));
+        assert_eq!(a.intersection(&b.translated(Point::new(2, 0))), None);
+        assert!(a.contains(Point::new(2, 2)));
+        assert!(!a.contains(Point::new(3, 2)));
+        assert!(!a.contains_rect(&b));
+        check_iterators(
+            b.points(),
+            [(2, 1), (3, 1), (4, 1)].into_iter().map(Point::from)
+        );
+    }
+
+    macro_rules! check_masked_matches_const
+    {
+        ($($mask:expr),* $(,)?) =>
+        {{
+            let size = Extents::new( 3, 4 );
+            for pos in size.index2_space().chain([Point::new(-1, 0), Point::new(3, 4)])
+            {
+                $(
+                    check_iterators(
+                        size.neighbours_masked(pos, $mask),
+                        size.neighbours::<{ $mask.bits() }>(pos)
+                    );
+                    check_iterators(
+                        size.stencil_neighbours(pos, &Stencil::from_neighbours($mask)),
+                        size.neighbours::<{ $mask.bits() }>(pos)
+                    );
+                )*
+            }
+        }};
+    }
+
+    #[test]
+    fn test_neighbours_masked()
+    {
+        let size = Extents::new( 3, 3 );
+        check_iterators(
+            size.neighbours_masked(Point::new(1, 1), Neighbours::Top | Neighbours::BottomLeft),
+            [(1, 0), (0, 2)].into_iter().map(Point::from)
+        );
+        check_iterators(
+            size.neighbours_masked(Point::new(0, 0), Neighbours::Diagonal),
+            [(1, 1)].into_iter().map(Point::from)
+        );
+        check_iterators(
+            size.neighbours_masked(Point::new(2, 1), Neighbours::Flush),
+            [(2, 0), (1, 1), (2, 2)].into_iter().map(Point::from)
+        );
+        assert_eq!(size.neighbours_masked(Point::new(1, 1), Neighbours::None).count(), 0);
+
+        check_masked_matches_const!(
+            Neighbours::None,
+            Neighbours::Top,
+            Neighbours::BottomRight,
+            Neighbours::Vertical,
+            Neighbours::Horizontal,
+            Neighbours::Flush,
+            Neighbours::Diagonal,
+            Neighbours::All,
+            Neighbours::Top.union(Neighbours::BottomLeft),
+        );
+    }
+
+    #[test]
+    fn test_stencils()
+    {
+        let size = Extents::new( 5, 5 );
+        check_iterators(
+            size.stencil_neighbours(Point::new(2, 2), &Stencil::radius(1)),
+            size.neighbours::<{ Neighbours::All.bits() }>(Point::new(2, 2))
+        );
+        assert_eq!(size.stencil_neighbours(Point::new(2, 2), &Stencil::radius(2)).count(), 24);
+        assert_eq!(size.stencil_neighbours(Point::new(0, 0), &Stencil::radius(2)).count(), 8);
+        check_iterators(
+            size.stencil_neighbours(Point::new(0, 0), &Stencil::knight()),
+            [(2, 1), (1, 2)].into_iter().map(Point::from)
         );
     }
 
@@ -190,17 +470,17 @@ mod tests
     {
         let size = Extents::new( 2, 2 );
         check_iterators(
-            size.neighbours::<{ Neighbours::All.bits() }>(0, 0),
+            size.neighbours::<{ Neighbours::All.bits() }>(Point::new(0, 0)),
             [
                 (1, 0), (0, 1), (1, 1)
-            ].into_iter()
+            ].into_iter().map(Point::from)
         );
 
         check_iterators(
-            size.neighbours::<{ Neighbours::All.bits() }>(1, 1),
+            size.neighbours::<{ Neighbours::All.bits() }>(Point::new(1, 1)),
             [
                 (0, 0), (1, 0), (0, 1)
-            ].into_iter()
+            ].into_iter().map(Point::from)
         );
     }
 }
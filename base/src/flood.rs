@@ -0,0 +1,180 @@
+use crate::array2::Array2;
+use crate::bitarray2::BitArray2;
+use crate::extents::Neighbours;
+use crate::extents::Point;
+
+use std::collections::VecDeque;
+
+// every cell reachable from start through cells matching the predicate, start included if it matches
+pub fn flood_fill<T, F>(
+    array: &Array2<T>,
+    start: Point,
+    neighbours: Neighbours,
+    mut predicate: F,
+) -> BitArray2
+where
+    F: FnMut(Point, &T) -> bool,
+{
+    let size = array.size();
+    let mut filled = BitArray2::from_size(size);
+    let mut open = vec![start];
+    while let Some(pos) = open.pop()
+    {
+        if filled.get_by_index2(pos) != Some(false) || !predicate(pos, &array[pos])
+        {
+            continue;
+        }
+        filled.set_by_index2(pos, true).expect("flood_fill -- pos is always valid here");
+        open.extend(size.neighbours_masked(pos, neighbours));
+    }
+    filled
+}
+
+// labels every group of connected cells matching the predicate with 1, 2, ... in row major order of discovery.
+// cells not matching the predicate are 0. returns the labels and the number of groups.
+pub fn connected_components<T, F>(
+    array: &Array2<T>,
+    neighbours: Neighbours,
+    mut predicate: F,
+) -> (Array2<u32>, u32)
+where
+    F: FnMut(Point, &T) -> bool,
+{
+    let size = array.size();
+    let matches = Array2::from_iter(array.enumerate().map(|(pos, e)| predicate(pos, e)), size)
+        .expect("connected_components -- sizes always match");
+
+    let mut labels = Array2::<u32>::from_size(size);
+    let mut count = 0;
+    let mut open = vec![];
+    for start in size.index2_space()
+    {
+        if !matches[start] || labels[start] != 0
+        {
+            continue;
+        }
+
+        count += 1;
+        labels[start] = count;
+        open.push(start);
+        while let Some(pos) = open.pop()
+        {
+            for neighbour in size.neighbours_masked(pos, neighbours)
+            {
+                if matches[neighbour] && labels[neighbour] == 0
+                {
+                    labels[neighbour] = count;
+                    open.push(neighbour);
+                }
+            }
+        }
+    }
+    (labels, count)
+}
+
+// breadth first number of steps from the nearest source through passable cells, None when unreachable.
+// sources are always at distance 0, even when they aren't passable themselves.
+pub fn distance_field<T, F>(
+    array: &Array2<T>,
+    sources: impl IntoIterator<Item = Point>,
+    neighbours: Neighbours,
+    mut passable: F,
+) -> Array2<Option<u32>>
+where
+    F: FnMut(Point, &T) -> bool,
+{
+    let size = array.size();
+    let mut distances = Array2::<Option<u32>>::from_size(size);
+    let mut open = VecDeque::new();
+    for source in sources
+    {
+        if let Some(distance) = distances.get_by_index2_mut(source)
+        {
+            if distance.is_none()
+            {
+                *distance = Some(0);
+                open.push_back(source);
+            }
+        }
+    }
+
+    while let Some(pos) = open.pop_front()
+    {
+        let next = distances[pos].map(|d| d + 1);
+        for neighbour in size.neighbours_masked(pos, neighbours)
+        {
+            if distances[neighbour].is_none() && passable(neighbour, &array[neighbour])
+            {
+                distances[neighbour] = next;
+                open.push_back(neighbour);
+            }
+        }
+    }
+    distances
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::extents::Extents;
+
+    // 1 marks land
+    fn islands() -> Array2<u8>
+    {
+        Array2::from_values(&[
+            1, 1, 0, 0,
+            0, 0, 0, 1,
+            0, 1, 0, 1,
+            1, 0, 0, 0,
+        ], Extents::new(4, 4)).unwrap()
+    }
+
+    #[test]
+    fn test_flood_fill()
+    {
+        let sea = |_: Point, cell: &u8| *cell == 0;
+        let filled = flood_fill(&islands(), Point::new(2, 0), Neighbours::Flush, sea);
+        assert_eq!(filled.count_ones(), 10);
+        assert!(filled[Point::new(0, 2)] && !filled[Point::new(0, 0)]);
+
+        let filled = flood_fill(&islands(), Point::new(0, 0), Neighbours::All, sea);
+        assert_eq!(filled.count_ones(), 0);
+
+        let land = |_: Point, cell: &u8| *cell == 1;
+        let filled = flood_fill(&islands(), Point::new(1, 2), Neighbours::Flush, land);
+        assert_eq!(filled.count_ones(), 1);
+        let filled = flood_fill(&islands(), Point::new(1, 2), Neighbours::All, land);
+        assert_eq!(filled.ones().collect::<Vec<_>>(), vec![Point::new(1, 2), Point::new(0, 3)]);
+    }
+
+    #[test]
+    fn test_connected_components()
+    {
+        let land = |_: Point, cell: &u8| *cell == 1;
+        let (labels, count) = connected_components(&islands(), Neighbours::Flush, land);
+        assert_eq!(count, 4);
+        assert_eq!(labels.raw_iter().copied().collect::<Vec<_>>(), vec![
+            1, 1, 0, 0,
+            0, 0, 0, 2,
+            0, 3, 0, 2,
+            4, 0, 0, 0,
+        ]);
+
+        let (_, count) = connected_components(&islands(), Neighbours::All, land);
+        assert_eq!(count, 3);
+    }
+
+    #[test]
+    fn test_distance_field()
+    {
+        let sea = |_: Point, cell: &u8| *cell == 0;
+        let distances = distance_field(&islands(), [Point::new(0, 0)], Neighbours::Flush, sea);
+        assert_eq!(distances[Point::new(0, 0)], Some(0));
+        assert_eq!(distances[Point::new(1, 0)], None);
+        assert_eq!(distances[Point::new(0, 1)], Some(1));
+        assert_eq!(distances[Point::new(3, 3)], Some(6));
+        assert_eq!(distances[Point::new(1, 3)], Some(6));
+        assert_eq!(distances[Point::new(0, 3)], None);
+    }
+}
@@ -1,8 +1,13 @@
 
 pub mod array2;
 pub mod assets;
+pub mod bitarray2;
 pub mod debug;
 pub mod extents;
+pub mod flood;
+pub mod migrate;
+pub mod path;
+pub mod sight;
 pub mod ronx;
 pub mod tuning;
 
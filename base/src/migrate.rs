@@ -0,0 +1,192 @@
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+use ron::Value;
+
+// upgrades a stored value from one version to the next, before it's deserialized into its type
+pub type Migration = fn(Value) -> Value;
+
+pub const VERSION_FIELD: &str = "version";
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum Error
+{
+    NotAStruct,
+    InvalidVersion,
+    NewerVersion{ found: u32, latest: u32 },
+}
+
+impl Display for Error
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result
+    {
+        match self
+        {
+            Error::NotAStruct => write!(f, "Migrate -- only structs carry a version"),
+            Error::InvalidVersion => write!(f, "Migrate -- the version isn't a positive integer"),
+            Error::NewerVersion{ found, latest } => write!(f, "Migrate -- version {found} is newer than the latest known version {latest}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+// a type stored with a version field, upgraded by its migrations when read
+pub trait Versioned
+{
+    // upgrade steps for files written by older code, see migrate
+    fn migrations() -> &'static [Migration]
+    {
+        &[]
+    }
+
+    // the version written by the current code, types store it in a `version` field
+    fn version() -> u32
+    {
+        latest_version(Self::migrations())
+    }
+}
+
+// the n-th migration upgrades version n to n + 1, so the latest version is the number of migrations
+pub fn latest_version(migrations: &[Migration]) -> u32
+{
+    migrations.len() as u32
+}
+
+// files written before versioning have no version field, they are version 0
+pub fn version(value: &Value) -> Result<u32, Error>
+{
+    let Value::Map(map) = value else
+    {
+        return Err(Error::NotAStruct);
+    };
+
+    let key = Value::String(VERSION_FIELD.into());
+    match map.iter().find(|(field, _)| **field == key)
+    {
+        None => Ok(0),
+        Some((_, Value::Number(number))) => number.as_i64()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or(Error::InvalidVersion),
+        Some(_) => Err(Error::InvalidVersion),
+    }
+}
+
+// runs every migration past the version of value, then stamps it with the latest version
+pub fn migrate(value: Value, migrations: &[Migration]) -> Result<Value, Error>
+{
+    let found = version(&value)?;
+    let latest = latest_version(migrations);
+    if found > latest
+    {
+        return Err(Error::NewerVersion{ found, latest });
+    }
+
+    let mut value = value;
+    for migration in &migrations[found as usize..]
+    {
+        value = migration(value);
+    }
+
+    let Value::Map(mut map) = value else
+    {
+        return Err(Error::NotAStruct);
+    };
+    map.insert(Value::String(VERSION_FIELD.into()), Value::Number(i64::from(latest).into()));
+    Ok(Value::Map(map))
+}
+
+// the first migration of a type that was stored before it was versioned. version 0 only lacks the
+// version field, which migrate adds.
+pub fn add_version(value: Value) -> Value
+{
+    value
+}
+
+// moves a field to a new name, does nothing when the field is missing
+pub fn rename_field(value: Value, from: &str, to: &str) -> Value
+{
+    let Value::Map(mut map) = value else
+    {
+        return value;
+    };
+
+    if let Some(field) = map.remove(&Value::String(from.into()))
+    {
+        map.insert(Value::String(to.into()), field);
+    }
+    Value::Map(map)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn rename_size(value: Value) -> Value
+    {
+        rename_field(value, "size", "cell_size")
+    }
+
+    fn double_cell_size(value: Value) -> Value
+    {
+        let Value::Map(mut map) = value else
+        {
+            return value;
+        };
+        let key = Value::String("cell_size".into());
+        if let Some(Value::Number(size)) = map.remove(&key)
+        {
+            map.insert(key, Value::Number((size.into_f64() * 2.0).into()));
+        }
+        Value::Map(map)
+    }
+
+    const MIGRATIONS: &[Migration] = &[rename_size, double_cell_size];
+
+    #[derive(Debug, PartialEq, serde::Deserialize)]
+    struct Board
+    {
+        version: u32,
+        cell_size: f32,
+    }
+
+    fn parse(text: &str) -> Value
+    {
+        ron::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn test_migrate_every_version()
+    {
+        let expected = Board{ version: 2, cell_size: 28.0 };
+
+        let v0 = migrate(parse("(size: 14.0)"), MIGRATIONS).unwrap();
+        assert_eq!(v0.into_rust::<Board>().unwrap(), expected);
+
+        let v1 = migrate(parse("(version: 1, cell_size: 14.0)"), MIGRATIONS).unwrap();
+        assert_eq!(v1.into_rust::<Board>().unwrap(), expected);
+
+        let v2 = migrate(parse("(version: 2, cell_size: 28.0)"), MIGRATIONS).unwrap();
+        assert_eq!(v2.into_rust::<Board>().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_add_version()
+    {
+        let v1 = migrate(parse("(cell_size: 14.0)"), &[add_version]).unwrap();
+        assert_eq!(v1.into_rust::<Board>().unwrap(), Board{ version: 1, cell_size: 14.0 });
+        // without it the file stays at version 0, as if nothing had changed since
+        assert_eq!(version(&migrate(parse("(cell_size: 14.0)"), &[]).unwrap()), Ok(0));
+    }
+
+    #[test]
+    fn test_migrate_errors()
+    {
+        assert_eq!(version(&parse("(cell_size: 1.0)")), Ok(0));
+        assert_eq!(migrate(parse("(version: 3)"), MIGRATIONS), Err(Error::NewerVersion{ found: 3, latest: 2 }));
+        assert_eq!(migrate(parse("(version: -1)"), MIGRATIONS), Err(Error::InvalidVersion));
+        assert_eq!(migrate(parse("(version: \"1\")"), MIGRATIONS), Err(Error::InvalidVersion));
+        assert_eq!(migrate(parse("[1, 2]"), MIGRATIONS), Err(Error::NotAStruct));
+    }
+}
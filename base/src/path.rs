@@ -0,0 +1,218 @@
+use crate::array2::Array2;
+use crate::extents::Extents;
+use crate::extents::Neighbours;
+use crate::extents::Point;
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Path
+{
+    // start to goal, both included
+    pub points: Vec<Point>,
+    pub cost: u32,
+}
+
+// cost is the price of entering a cell, None when the cell can't be entered.
+// a step always costs at least 1, which keeps the A* heuristic admissible.
+fn search<F, H>(
+    size: Extents,
+    start: Point,
+    goal: Point,
+    neighbours: Neighbours,
+    mut cost: F,
+    heuristic: H,
+) -> Option<Path>
+where
+    F: FnMut(Point) -> Option<u32>,
+    H: Fn(Point) -> u32,
+{
+    if !size.is_valid_pos(start) || !size.is_valid_pos(goal)
+    {
+        return None;
+    }
+
+    let mut best = Array2::<Option<u32>>::from_size(size);
+    let mut came_from = Array2::<Option<Point>>::from_size(size);
+    let mut open = BinaryHeap::new();
+    best[start] = Some(0);
+    // ties are broken on the heuristic, then row major position, so paths are deterministic
+    open.push(Reverse((heuristic(start), heuristic(start), start.y, start.x)));
+
+    while let Some(Reverse((_, _, y, x))) = open.pop()
+    {
+        let pos = Point::new(x, y);
+        let pos_cost = best[pos].expect("path -- open cells always have a cost");
+        if pos == goal
+        {
+            let mut points = vec![goal];
+            while let Some(previous) = came_from[*points.last().unwrap()]
+            {
+                points.push(previous);
+            }
+            points.reverse();
+            return Some(Path
+            {
+                points,
+                cost: pos_cost,
+            });
+        }
+
+        for neighbour in size.neighbours_masked(pos, neighbours)
+        {
+            let Some(step) = cost(neighbour) else
+            {
+                continue;
+            };
+            let neighbour_cost = pos_cost.saturating_add(step.max(1));
+            if best[neighbour].is_some_and(|known| known <= neighbour_cost)
+            {
+                continue;
+            }
+            best[neighbour] = Some(neighbour_cost);
+            came_from[neighbour] = Some(pos);
+            let h = heuristic(neighbour);
+            open.push(Reverse((neighbour_cost.saturating_add(h), h, neighbour.y, neighbour.x)));
+        }
+    }
+    None
+}
+
+pub fn dijkstra<F>(
+    size: Extents,
+    start: Point,
+    goal: Point,
+    neighbours: Neighbours,
+    cost: F,
+) -> Option<Path>
+where
+    F: FnMut(Point) -> Option<u32>,
+{
+    search(size, start, goal, neighbours, cost, |_| 0)
+}
+
+pub fn astar<F>(
+    size: Extents,
+    start: Point,
+    goal: Point,
+    neighbours: Neighbours,
+    cost: F,
+) -> Option<Path>
+where
+    F: FnMut(Point) -> Option<u32>,
+{
+    let diagonal = neighbours.intersects(Neighbours::Diagonal);
+    let heuristic = |pos: Point|
+    {
+        let delta = (goal - pos).abs();
+        if diagonal
+        {
+            delta.max_element() as u32
+        }
+        else
+        {
+            (delta.x + delta.y) as u32
+        }
+    };
+    search(size, start, goal, neighbours, cost, heuristic)
+}
+
+// cheapest cost from the nearest source to every cell, None when unreachable
+pub fn dijkstra_map<F>(
+    size: Extents,
+    sources: impl IntoIterator<Item = Point>,
+    neighbours: Neighbours,
+    mut cost: F,
+) -> Array2<Option<u32>>
+where
+    F: FnMut(Point) -> Option<u32>,
+{
+    let mut best = Array2::<Option<u32>>::from_size(size);
+    let mut open = BinaryHeap::new();
+    for source in sources
+    {
+        if let Some(known) = best.get_by_index2_mut(source)
+        {
+            *known = Some(0);
+            open.push(Reverse((0, source.y, source.x)));
+        }
+    }
+
+    while let Some(Reverse((pos_cost, y, x))) = open.pop()
+    {
+        let pos = Point::new(x, y);
+        if best[pos].is_some_and(|known| known < pos_cost)
+        {
+            continue;
+        }
+        for neighbour in size.neighbours_masked(pos, neighbours)
+        {
+            let Some(step) = cost(neighbour) else
+            {
+                continue;
+            };
+            let neighbour_cost = pos_cost.saturating_add(step.max(1));
+            if best[neighbour].is_some_and(|known| known <= neighbour_cost)
+            {
+                continue;
+            }
+            best[neighbour] = Some(neighbour_cost);
+            open.push(Reverse((neighbour_cost, neighbour.y, neighbour.x)));
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    // 0 is water, 9 is an island, anything else is shallows that slow the ship down
+    fn sea() -> Array2<u32>
+    {
+        Array2::from_values(&[
+            1, 1, 1, 1, 1,
+            1, 9, 9, 9, 1,
+            1, 1, 5, 9, 1,
+            9, 9, 1, 1, 1,
+        ], Extents::new(5, 4)).unwrap()
+    }
+
+    fn cost(sea: &Array2<u32>) -> impl FnMut(Point) -> Option<u32> + '_
+    {
+        |pos| match sea[pos] { 9 => None, c => Some(c) }
+    }
+
+    #[test]
+    fn test_astar_matches_dijkstra()
+    {
+        let sea = sea();
+        let start = Point::new(0, 2);
+        let goal = Point::new(2, 3);
+
+        let flush = astar(sea.size(), start, goal, Neighbours::Flush, cost(&sea)).unwrap();
+        let reference = dijkstra(sea.size(), start, goal, Neighbours::Flush, cost(&sea)).unwrap();
+        assert_eq!(flush.cost, reference.cost);
+        assert_eq!(flush.cost, 7);
+        assert_eq!(flush.points.first(), Some(&start));
+        assert_eq!(flush.points.last(), Some(&goal));
+
+        let all = astar(sea.size(), start, goal, Neighbours::All, cost(&sea)).unwrap();
+        assert_eq!(all.cost, 2);
+        assert_eq!(all.points, vec![start, Point::new(1, 2), goal]);
+    }
+
+    #[test]
+    fn test_unreachable()
+    {
+        let sea = sea();
+        assert_eq!(astar(sea.size(), Point::new(0, 0), Point::new(0, 3), Neighbours::All, cost(&sea)), None);
+        assert_eq!(dijkstra(sea.size(), Point::new(0, 0), Point::new(9, 9), Neighbours::All, cost(&sea)), None);
+
+        let map = dijkstra_map(sea.size(), [Point::new(0, 0)], Neighbours::Flush, cost(&sea));
+        assert_eq!(map[Point::new(4, 3)], Some(7));
+        assert_eq!(map[Point::new(1, 1)], None);
+    }
+}
@@ -1,18 +1,536 @@
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::future::Future;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::atomic::AtomicU32;
+use std::sync::atomic::Ordering;
+use std::task::Poll;
 
-pub fn write_sync<T: serde::Serialize>(data: &T, file_path: &std::path::Path) -> std::io::Result<()>
+use ron::error::SpannedError;
+use ron::Value;
+
+// a top level field listing other files of the same shape, merged in order under the fields of the file
+//   include: "base.ron",
+//   include: ["base.ron", "/tuning/shared.ron"],
+pub const INCLUDE_FIELD: &str = "include";
+
+// a RON error with the file it came from and the offending line, for errors designers have to fix by hand
+#[derive(Debug)]
+pub struct ParseError
+{
+    pub path: PathBuf,
+    pub error: SpannedError,
+    pub excerpt: Option<String>,
+}
+
+impl ParseError
+{
+    pub fn new(path: impl Into<PathBuf>, text: &str, error: SpannedError) -> Self
+    {
+        let excerpt = excerpt(text, error.position.line, error.position.col);
+        Self
+        {
+            path: path.into(),
+            error,
+            excerpt,
+        }
+    }
+}
+
+impl Display for ParseError
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result
+    {
+        let position = self.error.position;
+        write!(f, "[{}] line {}, column {}: {}", self.path.display(), position.line, position.col, self.error.code)?;
+        if let Some(excerpt) = &self.excerpt
+        {
+            write!(f, "\n{excerpt}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+// a value with its includes merged in or migrated is no longer the text of any one file,
+// so its errors carry the path of the file it was read from but no position
+#[derive(Debug)]
+pub struct ValueError
+{
+    pub path: PathBuf,
+    pub error: ron::Error,
+}
+
+impl Display for ValueError
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result
+    {
+        write!(f, "[{}] with its includes and migrations applied: {}", self.path.display(), self.error)
+    }
+}
+
+impl std::error::Error for ValueError {}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum IncludeError
+{
+    NotAPath,
+    Cycle(PathBuf),
+}
+
+impl Display for IncludeError
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result
+    {
+        match self
+        {
+            IncludeError::NotAPath => write!(f, "Include -- {INCLUDE_FIELD} takes a path or a list of paths"),
+            IncludeError::Cycle(path) => write!(f, "Include -- [{}] includes itself", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for IncludeError {}
+
+// where included files come from. the sync reader reads them from a folder, the bevy loader reads them
+// through its LoadContext so they become dependencies of the asset including them.
+pub trait IncludeReader: Send
+{
+    type Error: From<IncludeError>;
+
+    // the value of the file at file_path, which is relative to the assets folder
+    fn read_include(&mut self, file_path: &Path) -> impl Future<Output = Result<Value, Self::Error>> + Send;
+}
+
+struct FolderReader<'a>
+{
+    folder: &'a Path,
+}
+
+impl IncludeReader for FolderReader<'_>
+{
+    type Error = std::io::Error;
+
+    async fn read_include(&mut self, file_path: &Path) -> std::io::Result<Value>
+    {
+        read_sync_in(self.folder, file_path)
+    }
+}
+
+impl From<IncludeError> for std::io::Error
+{
+    fn from(error: IncludeError) -> Self
+    {
+        std::io::Error::other(error)
+    }
+}
+
+// the line of text with a caret under the column, both start at 1 and the column counts bytes like RON does
+//   3 |     cell_size: (28.0 28.0),
+//     |                      ^
+pub fn excerpt(text: &str, line: usize, col: usize) -> Option<String>
+{
+    let source = text.lines().nth(line.checked_sub(1)?)?.trim_end_matches('\r');
+    let mut end = col.saturating_sub(1).min(source.len());
+    while !source.is_char_boundary(end)
+    {
+        end -= 1;
+    }
+    // tabs stay tabs so the caret lines up whatever the tab width
+    let indent = source[..end].chars().map(|c| if c == '\t' { '\t' } else { ' ' }).collect::<String>();
+    let number = line.to_string();
+    let gutter = " ".repeat(number.len());
+    Some(format!("{number} | {source}\n{gutter} | {indent}^"))
+}
+
+// file_path is relative to the assets folder, see assets::assets_folder
+pub fn write_sync<T: serde::Serialize>(data: &T, file_path: &Path) -> std::io::Result<()>
 {
-    let full_path = std::path::Path::new(crate::assets::ASSETS_FOLDER).join(file_path);
-    let file = std::fs::File::create(full_path)?;
-    let writer = std::io::BufWriter::new(file);
+    write_sync_in(data, &crate::assets::assets_folder(), file_path)
+}
+
+pub fn read_sync<T: for<'de> serde::Deserialize<'de>>(file_path: &Path) -> std::io::Result<T>
+{
+    read_sync_in(&crate::assets::assets_folder(), file_path)
+}
+
+// reads a versioned file, upgrading it to the latest version before the typed deserialize
+pub fn read_migrated_sync<T: for<'de> serde::Deserialize<'de>>(file_path: &Path, migrations: &[crate::migrate::Migration]) -> std::io::Result<T>
+{
+    read_migrated_sync_in(&crate::assets::assets_folder(), file_path, migrations)
+}
+
+// the value of a file with its includes merged in, and whether it had any
+pub fn read_value_sync(file_path: &Path) -> std::io::Result<(Value, bool)>
+{
+    read_value_sync_in(&crate::assets::assets_folder(), file_path)
+}
+
+// the data is written next to the file first then renamed over it, so a crash mid-save leaves either
+// the old or the new file but never a truncated one
+pub fn write_sync_in<T: serde::Serialize>(data: &T, folder: &Path, file_path: &Path) -> std::io::Result<()>
+{
+    let full_path = folder.join(file_path);
     let pretty = ron::ser::PrettyConfig::default();
-    ron::ser::to_writer_pretty(writer, data, pretty).map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))
+    let text = ron::ser::to_string_pretty(data, pretty).map_err(std::io::Error::other)?;
+
+    if let Some(parent) = full_path.parent()
+    {
+        std::fs::create_dir_all(parent)?;
+    }
+    replace_file(&temp_path(&full_path)?, &full_path, text.as_bytes())
+}
+
+pub fn read_sync_in<T: for<'de> serde::Deserialize<'de>>(folder: &Path, file_path: &Path) -> std::io::Result<T>
+{
+    let full_path = folder.join(file_path);
+    let text = std::fs::read_to_string(&full_path)?;
+    ron::from_str(&text).map_err(|error| std::io::Error::other(ParseError::new(full_path, &text, error)))
+}
+
+pub fn read_migrated_sync_in<T: for<'de> serde::Deserialize<'de>>(folder: &Path, file_path: &Path, migrations: &[crate::migrate::Migration]) -> std::io::Result<T>
+{
+    let (value, _) = read_value_sync_in(folder, file_path)?;
+    let value = crate::migrate::migrate(value, migrations).map_err(std::io::Error::other)?;
+    from_value(folder.join(file_path), value).map_err(std::io::Error::other)
 }
 
-pub fn read_sync<T: for<'de> serde::Deserialize<'de>>(file_path: &std::path::Path) -> std::io::Result<T>
+// deserializes a value read from path, after its includes and migrations were applied
+pub fn from_value<T: for<'de> serde::Deserialize<'de>>(path: impl Into<PathBuf>, value: Value) -> Result<T, ValueError>
 {
-    let full_path = std::path::Path::new(crate::assets::ASSETS_FOLDER).join(file_path);
-    let file = std::fs::File::open(full_path)?;
-    let reader = std::io::BufReader::new(file);
-    ron::de::from_reader(reader).map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))
+    value.into_rust().map_err(|error| ValueError{ path: path.into(), error })
 }
 
+pub fn read_value_sync_in(folder: &Path, file_path: &Path) -> std::io::Result<(Value, bool)>
+{
+    let value = read_sync_in::<Value>(folder, file_path)?;
+    ready(include_files(&mut FolderReader{ folder }, file_path, value))
+}
+
+// merges the files listed in the include field of value under its other fields, see INCLUDE_FIELD.
+// value was read from file_path, the result tells whether it had any includes.
+pub async fn include_files<R: IncludeReader>(reader: &mut R, file_path: &Path, value: Value) -> Result<(Value, bool), R::Error>
+{
+    include_files_in(reader, file_path.to_path_buf(), value, &mut Vec::new()).await
+}
+
+// boxed as async functions can't call themselves otherwise
+type IncludeFuture<'a, E> = Pin<Box<dyn Future<Output = Result<(Value, bool), E>> + Send + 'a>>;
+
+// chain holds the files including this one, to stop on cycles instead of overflowing the stack
+fn include_files_in<'a, R: IncludeReader>(reader: &'a mut R, file_path: PathBuf, mut value: Value, chain: &'a mut Vec<PathBuf>) -> IncludeFuture<'a, R::Error>
+{
+    Box::pin(async move
+    {
+        let includes = take_includes(&mut value)?;
+        if includes.is_empty()
+        {
+            return Ok((value, false));
+        }
+
+        chain.push(file_path.clone());
+        let mut merged = Value::Map(Default::default());
+        for include in includes
+        {
+            let include_path = resolve_include(&file_path, &include);
+            if chain.contains(&include_path)
+            {
+                return Err(IncludeError::Cycle(include_path).into());
+            }
+            let included = reader.read_include(&include_path).await?;
+            let (included, _) = include_files_in(reader, include_path, included, chain).await?;
+            merge(&mut merged, included, None);
+        }
+        chain.pop();
+
+        merge(&mut merged, value, None);
+        Ok((merged, true))
+    })
+}
+
+// runs the future of a sync reader, which never has to wait
+fn ready<T>(future: impl Future<Output = T>) -> T
+{
+    let mut future = std::pin::pin!(future);
+    match future.as_mut().poll(&mut std::task::Context::from_waker(std::task::Waker::noop()))
+    {
+        Poll::Ready(output) => output,
+        Poll::Pending => unreachable!("ronx -- sync include readers are always ready"),
+    }
+}
+
+// removes the include field from a struct value, returning the paths it listed
+pub fn take_includes(value: &mut Value) -> Result<Vec<String>, IncludeError>
+{
+    let Value::Map(map) = value else
+    {
+        return Ok(Vec::new());
+    };
+
+    match map.remove(&Value::String(INCLUDE_FIELD.into()))
+    {
+        None => Ok(Vec::new()),
+        Some(Value::String(path)) => Ok(vec![path]),
+        Some(Value::Seq(paths)) => paths.into_iter()
+            .map(|path| match path
+            {
+                Value::String(path) => Ok(path),
+                _ => Err(IncludeError::NotAPath),
+            })
+            .collect(),
+        Some(_) => Err(IncludeError::NotAPath),
+    }
+}
+
+// include paths are relative to the folder of the including file, or to the assets folder when they start with /.
+// . and .. are folded away so the same file is always spelled the same way.
+pub fn resolve_include(file_path: &Path, include: &str) -> PathBuf
+{
+    let joined = match include.strip_prefix('/')
+    {
+        Some(rooted) => PathBuf::from(rooted),
+        None => file_path.parent().unwrap_or(Path::new("")).join(include),
+    };
+
+    let mut resolved = PathBuf::new();
+    for component in joined.components()
+    {
+        match component
+        {
+            std::path::Component::CurDir => {},
+            std::path::Component::ParentDir => { resolved.pop(); },
+            component => resolved.push(component),
+        }
+    }
+    resolved
+}
+
+// told the path, such as "grid.uv_width", and the new value of every field a merge adds or replaces
+pub type OnField<'a> = &'a mut dyn FnMut(&str, &Value);
+
+// merges the fields of from into into, structs are merged field by field and anything else is replaced
+pub fn merge(into: &mut Value, from: Value, mut on_field: Option<OnField>)
+{
+    merge_at(into, from, "", &mut on_field);
+}
+
+fn merge_at(into: &mut Value, from: Value, path: &str, on_field: &mut Option<OnField>)
+{
+    match (into, from)
+    {
+        (Value::Map(into_map), Value::Map(from_map)) =>
+        {
+            for (key, field) in from_map
+            {
+                let field_path = join_path(path, &key);
+                if into_map.iter().any(|(into_key, _)| *into_key == key)
+                {
+                    merge_at(&mut into_map[&key], field, &field_path, on_field);
+                }
+                else
+                {
+                    if let Some(on_field) = on_field
+                    {
+                        on_field(&field_path, &field);
+                    }
+                    into_map.insert(key, field);
+                }
+            }
+        },
+        (into, from) =>
+        {
+            *into = from;
+            if let Some(on_field) = on_field
+            {
+                on_field(path, into);
+            }
+        },
+    }
+}
+
+// the path of a field of the struct at path, keys that aren't names are written as RON
+pub fn join_path(path: &str, key: &Value) -> String
+{
+    let key = match key
+    {
+        Value::String(key) => key.clone(),
+        key => ron::to_string(key).unwrap_or_default(),
+    };
+    if path.is_empty()
+    {
+        key
+    }
+    else
+    {
+        format!("{path}.{key}")
+    }
+}
+
+// board_vis.ron to board_vis.ron.tmp, in the same folder so the rename never crosses file systems
+// unique per save, two saves of the same file at once each write their own temp file and the last rename wins
+fn temp_path(full_path: &Path) -> std::io::Result<PathBuf>
+{
+    static COUNTER: AtomicU32 = AtomicU32::new(0);
+
+    let Some(file_name) = full_path.file_name() else
+    {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("[{}] is not a file path", full_path.display())));
+    };
+    let mut temp_name = file_name.to_os_string();
+    temp_name.push(format!(".{}.{}.tmp", std::process::id(), COUNTER.fetch_add(1, Ordering::Relaxed)));
+    Ok(full_path.with_file_name(temp_name))
+}
+
+// the temp file is removed again when anything fails, the file at full_path is only ever replaced whole
+fn replace_file(temp_path: &Path, full_path: &Path, bytes: &[u8]) -> std::io::Result<()>
+{
+    let written = write_temp(temp_path, bytes).and_then(|_| std::fs::rename(temp_path, full_path));
+    if written.is_err()
+    {
+        let _ = std::fs::remove_file(temp_path);
+    }
+    written
+}
+
+fn write_temp(temp_path: &Path, bytes: &[u8]) -> std::io::Result<()>
+{
+    let mut file = std::fs::File::options().write(true).create_new(true).open(temp_path)?;
+    file.write_all(bytes)?;
+    file.sync_all()
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+    struct Save
+    {
+        level: u32,
+        name: String,
+    }
+
+    fn test_folder(name: &str) -> PathBuf
+    {
+        let folder = std::env::temp_dir().join(format!("ronx_{}_{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&folder);
+        folder
+    }
+
+    #[test]
+    fn test_write_read_in_folder()
+    {
+        let folder = test_folder("round_trip");
+        let file_path = Path::new("saves/slot_0.ron");
+        let save = Save{ level: 3, name: "cim".into() };
+        write_sync_in(&save, &folder, file_path).unwrap();
+        assert_eq!(read_sync_in::<Save>(&folder, file_path).unwrap(), save);
+
+        let overwrite = Save{ level: 4, name: "cim".into() };
+        write_sync_in(&overwrite, &folder, file_path).unwrap();
+        assert_eq!(read_sync_in::<Save>(&folder, file_path).unwrap(), overwrite);
+
+        let files = std::fs::read_dir(folder.join("saves")).unwrap().map(|entry| entry.unwrap().file_name()).collect::<Vec<_>>();
+        assert_eq!(files, vec![std::ffi::OsString::from("slot_0.ron")]);
+        std::fs::remove_dir_all(&folder).unwrap();
+    }
+
+    #[test]
+    fn test_parse_error_excerpt()
+    {
+        let folder = test_folder("parse_error");
+        let file_path = Path::new("slot_0.ron");
+        std::fs::create_dir_all(&folder).unwrap();
+        std::fs::write(folder.join(file_path), "(\r\n\tlevel: 3,\r\n\tname: cim,\r\n)").unwrap();
+
+        let err = read_sync_in::<Save>(&folder, file_path).unwrap_err();
+        let parse_error = err.get_ref().and_then(|inner| inner.downcast_ref::<ParseError>()).unwrap();
+        assert_eq!(parse_error.error.position.line, 3);
+        assert_eq!(parse_error.excerpt.as_deref(), Some("3 | \tname: cim,\n  | \t      ^"));
+        assert!(err.to_string().starts_with(&format!("[{}] line 3, column 8: ", folder.join(file_path).display())));
+        std::fs::remove_dir_all(&folder).unwrap();
+    }
+
+    #[test]
+    fn test_excerpt()
+    {
+        let text = "(\n    cell_size: (28.0 28.0),\n)";
+        assert_eq!(excerpt(text, 2, 22).unwrap(), "2 |     cell_size: (28.0 28.0),\n  |                      ^");
+        assert_eq!(excerpt("(é: 1)", 1, 4).unwrap(), "1 | (é: 1)\n  |   ^");
+        assert_eq!(excerpt(text, 9, 1), None);
+        assert_eq!(excerpt(text, 0, 0), None);
+    }
+
+    #[test]
+    fn test_read_includes()
+    {
+        let folder = test_folder("includes");
+        std::fs::create_dir_all(folder.join("saves/shared")).unwrap();
+        std::fs::write(folder.join("saves/shared/base.ron"), "(level: 1, name: \"base\")").unwrap();
+        std::fs::write(folder.join("saves/level.ron"), "(include: \"shared/base.ron\", level: 2)").unwrap();
+        std::fs::write(folder.join("saves/slot_0.ron"), "(include: [\"level.ron\"], name: \"cim\")").unwrap();
+
+        let (value, included) = read_value_sync_in(&folder, Path::new("saves/slot_0.ron")).unwrap();
+        assert!(included);
+        assert_eq!(value.into_rust::<Save>().unwrap(), Save{ level: 2, name: "cim".into() });
+        let (_, included) = read_value_sync_in(&folder, Path::new("saves/shared/base.ron")).unwrap();
+        assert!(!included);
+
+        std::fs::write(folder.join("saves/shared/base.ron"), "(include: \"/saves/slot_0.ron\")").unwrap();
+        let err = read_value_sync_in(&folder, Path::new("saves/slot_0.ron")).unwrap_err();
+        let include_error = err.get_ref().and_then(|inner| inner.downcast_ref::<IncludeError>()).unwrap();
+        assert_eq!(*include_error, IncludeError::Cycle(PathBuf::from("saves/slot_0.ron")));
+
+        std::fs::write(folder.join("saves/slot_0.ron"), "(include: 3)").unwrap();
+        assert!(read_value_sync_in(&folder, Path::new("saves/slot_0.ron")).is_err());
+        std::fs::remove_dir_all(&folder).unwrap();
+    }
+
+    #[test]
+    fn test_resolve_include()
+    {
+        let file_path = Path::new("tuning/board_vis.ron");
+        assert_eq!(resolve_include(file_path, "base.ron"), PathBuf::from("tuning/base.ron"));
+        assert_eq!(resolve_include(file_path, "./shared/../base.ron"), PathBuf::from("tuning/base.ron"));
+        assert_eq!(resolve_include(file_path, "../base.ron"), PathBuf::from("base.ron"));
+        assert_eq!(resolve_include(file_path, "/levels/base.ron"), PathBuf::from("levels/base.ron"));
+        assert_eq!(resolve_include(Path::new("base.ron"), "other.ron"), PathBuf::from("other.ron"));
+    }
+
+    #[test]
+    fn test_failed_write_keeps_file()
+    {
+        let folder = test_folder("failed_write");
+        let file_path = Path::new("slot_0.ron");
+        let save = Save{ level: 3, name: "cim".into() };
+        write_sync_in(&save, &folder, file_path).unwrap();
+
+        // a folder in the way of the temp file makes the write fail before the rename
+        let in_the_way = folder.join("in_the_way.tmp");
+        std::fs::create_dir(&in_the_way).unwrap();
+        assert!(replace_file(&in_the_way, &folder.join(file_path), b"(level: 4)").is_err());
+        assert_eq!(read_sync_in::<Save>(&folder, file_path).unwrap(), save);
+        std::fs::remove_dir_all(&folder).unwrap();
+    }
+
+    #[test]
+    fn test_failed_rename_removes_temp()
+    {
+        let folder = test_folder("failed_rename");
+        let full_path = folder.join("slot_0.ron");
+        let temp = temp_path(&full_path).unwrap();
+        assert_ne!(temp, temp_path(&full_path).unwrap());
+
+        // a folder with files in it can't be replaced by a file
+        std::fs::create_dir_all(full_path.join("inner")).unwrap();
+        assert!(replace_file(&temp, &full_path, b"(level: 4)").is_err());
+        assert!(!temp.exists());
+        assert!(full_path.join("inner").is_dir());
+        std::fs::remove_dir_all(&folder).unwrap();
+    }
+}
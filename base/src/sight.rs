@@ -0,0 +1,243 @@
+use crate::bitarray2::BitArray2;
+use crate::extents::Extents;
+use crate::extents::Point;
+
+// bresenham line from start to end, both included
+#[derive(Debug, Clone)]
+pub struct Line
+{
+    pos: Point,
+    end: Point,
+    delta: Point,
+    step: Point,
+    error: i32,
+    done: bool,
+}
+
+impl Line
+{
+    pub fn new(start: Point, end: Point) -> Self
+    {
+        let delta = Point::new((end.x - start.x).abs(), -(end.y - start.y).abs());
+        Line {
+            pos: start,
+            end,
+            delta,
+            step: Point::new((end.x - start.x).signum(), (end.y - start.y).signum()),
+            error: delta.x + delta.y,
+            done: false,
+        }
+    }
+}
+
+impl Iterator for Line
+{
+    type Item = Point;
+
+    fn next(&mut self) -> Option<Self::Item>
+    {
+        if self.done
+        {
+            return None;
+        }
+
+        let pos = self.pos;
+        if pos == self.end
+        {
+            self.done = true;
+            return Some(pos);
+        }
+
+        let error2 = 2 * self.error;
+        if error2 >= self.delta.y
+        {
+            self.error += self.delta.y;
+            self.pos.x += self.step.x;
+        }
+        if error2 <= self.delta.x
+        {
+            self.error += self.delta.x;
+            self.pos.y += self.step.y;
+        }
+        Some(pos)
+    }
+}
+
+pub fn line(start: Point, end: Point) -> Line
+{
+    Line::new(start, end)
+}
+
+// a slope as an exact fraction, the denominator is always positive
+#[derive(Debug, Clone, Copy)]
+struct Slope
+{
+    num: i32,
+    den: i32,
+}
+
+impl Slope
+{
+    // the slope of the left edge of a tile
+    fn of_tile(depth: i32, col: i32) -> Self
+    {
+        Slope{ num: 2 * col - 1, den: 2 * depth }
+    }
+
+    // depth * slope, rounded with ties going up
+    fn round_ties_up(&self, depth: i32) -> i32
+    {
+        (2 * depth * self.num + self.den).div_euclid(2 * self.den)
+    }
+
+    // depth * slope, rounded with ties going down
+    fn round_ties_down(&self, depth: i32) -> i32
+    {
+        -(self.den - 2 * depth * self.num).div_euclid(2 * self.den)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Row
+{
+    depth: i32,
+    start: Slope,
+    end: Slope,
+}
+
+impl Row
+{
+    fn next(&self) -> Row
+    {
+        Row{ depth: self.depth + 1, ..*self }
+    }
+
+    fn is_symmetric(&self, col: i32) -> bool
+    {
+        col * self.start.den >= self.depth * self.start.num && col * self.end.den <= self.depth * self.end.num
+    }
+}
+
+// symmetric shadowcasting: a cell is visible from origin when the origin is visible from it.
+// cells outside of size block vision, radius limits the view to a circle when set.
+pub fn field_of_view<F>(size: Extents, origin: Point, radius: Option<i32>, mut blocks: F) -> BitArray2
+where
+    F: FnMut(Point) -> bool,
+{
+    let mut visible = BitArray2::from_size(size);
+    if visible.set_by_index2(origin, true).is_err()
+    {
+        return visible;
+    }
+
+    // north, east, south and west, as (depth, col) to board space
+    let quadrants: [fn(Point, i32, i32) -> Point; 4] = [
+        |o, depth, col| Point::new(o.x + col, o.y - depth),
+        |o, depth, col| Point::new(o.x + depth, o.y + col),
+        |o, depth, col| Point::new(o.x + col, o.y + depth),
+        |o, depth, col| Point::new(o.x - depth, o.y + col),
+    ];
+
+    for transform in quadrants
+    {
+        let mut is_wall = |depth: i32, col: i32| -> bool
+        {
+            let pos = transform(origin, depth, col);
+            !size.is_valid_pos(pos) || blocks(pos)
+        };
+
+        let mut rows = vec![Row{ depth: 1, start: Slope{ num: -1, den: 1 }, end: Slope{ num: 1, den: 1 } }];
+        while let Some(mut row) = rows.pop()
+        {
+            if radius.is_some_and(|radius| row.depth > radius)
+            {
+                continue;
+            }
+
+            let mut prev_wall = None;
+            for col in row.start.round_ties_up(row.depth)..=row.end.round_ties_down(row.depth)
+            {
+                let wall = is_wall(row.depth, col);
+                let in_radius = radius.is_none_or(|radius| col * col + row.depth * row.depth <= radius * radius);
+                if in_radius && (wall || row.is_symmetric(col))
+                {
+                    let _ = visible.set_by_index2(transform(origin, row.depth, col), true);
+                }
+
+                if prev_wall == Some(true) && !wall
+                {
+                    row.start = Slope::of_tile(row.depth, col);
+                }
+                if prev_wall == Some(false) && wall
+                {
+                    let mut next = row.next();
+                    next.end = Slope::of_tile(row.depth, col);
+                    rows.push(next);
+                }
+                prev_wall = Some(wall);
+            }
+
+            if prev_wall == Some(false)
+            {
+                rows.push(row.next());
+            }
+        }
+    }
+    visible
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn test_line()
+    {
+        assert_eq!(line(Point::new(0, 0), Point::new(3, 1)).collect::<Vec<_>>(), vec![
+            Point::new(0, 0), Point::new(1, 0), Point::new(2, 1), Point::new(3, 1),
+        ]);
+        assert_eq!(line(Point::new(2, 2), Point::new(2, 2)).collect::<Vec<_>>(), vec![Point::new(2, 2)]);
+        assert_eq!(line(Point::new(0, 3), Point::new(0, 0)).count(), 4);
+        assert_eq!(line(Point::new(3, 3), Point::new(0, 0)).collect::<Vec<_>>(), vec![
+            Point::new(3, 3), Point::new(2, 2), Point::new(1, 1), Point::new(0, 0),
+        ]);
+    }
+
+    #[test]
+    fn test_field_of_view()
+    {
+        let size = Extents::new(7, 7);
+        let wall = Point::new(3, 2);
+        let visible = field_of_view(size, Point::new(3, 3), None, |pos| pos == wall);
+        assert!(visible[wall]);
+        assert!(!visible[Point::new(3, 1)]);
+        assert!(!visible[Point::new(3, 0)]);
+        assert!(visible[Point::new(0, 0)]);
+        assert!(visible[Point::new(6, 6)]);
+
+        let limited = field_of_view(size, Point::new(3, 3), Some(2), |_| false);
+        assert!(limited[Point::new(3, 1)]);
+        assert!(!limited[Point::new(3, 0)]);
+        assert!(!limited[Point::new(1, 1)]);
+    }
+
+    #[test]
+    fn test_field_of_view_symmetric()
+    {
+        let size = Extents::new(9, 7);
+        let blocked = [(2, 1), (5, 2), (3, 4), (6, 5), (1, 5), (7, 1)].map(Point::from);
+        let blocks = |pos: Point| blocked.contains(&pos);
+        for a in size.index2_space().filter(|pos| !blocks(*pos))
+        {
+            let from_a = field_of_view(size, a, None, blocks);
+            for b in size.index2_space().filter(|pos| !blocks(*pos))
+            {
+                if from_a[b]
+                {
+                    assert!(field_of_view(size, b, None, blocks)[a], "{a} sees {b}");
+                }
+            }
+        }
+    }
+}
@@ -1,39 +1,630 @@
-
 use crate::debug_name;
+use crate::migrate::Versioned;
+
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::path::PathBuf;
+
+use ron::Value;
+
+// CIM_TUNING_board_vis.cell_size=32,32
+pub const OVERRIDE_ENV_PREFIX: &str = "CIM_TUNING_";
+// --tuning board_vis.cell_size=32,32
+pub const OVERRIDE_ARG: &str = "--tuning";
 
-pub trait Tuning
+// where the value of a field was resolved from, each layer wins over the ones before it
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Layer
 {
-    fn path() -> &'static std::path::Path;
+    Default,
+    Asset,
+    User,
+    Override,
+}
 
-    fn load() -> Self where Self: Sized + Default, for<'de> Self: serde::Deserialize<'de>
+impl Display for Layer
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result
     {
-        match crate::ronx::read_sync(Self::path())
+        match self
         {
-            Ok(tuning) =>
-            {
-                tuning
-            },
-            Err(err) =>
-            {
-                eprintln!("{} -- Failed to load [{}] with error [{}]", debug_name!(), Self::path().display(), err);
-                debug_assert!(false, "Failed to load [{}]", Self::path().display());
-                Self::default()
-            }
+            Layer::Default => write!(f, "default"),
+            Layer::Asset => write!(f, "asset"),
+            Layer::User => write!(f, "user"),
+            Layer::Override => write!(f, "override"),
         }
     }
+}
+
+// the layer of every leaf field, by path such as "grid.uv_width"
+pub type Sources = BTreeMap<String, Layer>;
+
+#[derive(Debug, Clone)]
+pub struct Layered<T>
+{
+    pub tuning: T,
+    pub sources: Sources,
+    // the asset file is older or lists fewer fields than the current code, Tuning::update_asset rewrites it
+    pub outdated: bool,
+}
+
+// a problem with the value of one field, by path such as "adjacency_images[3]"
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct FieldError
+{
+    pub field: String,
+    pub message: String,
+}
 
-    fn save(&self) where Self: Sized + Default + serde::Serialize
+impl FieldError
+{
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self
     {
-        match crate::ronx::write_sync(&self, Self::path())
+        Self
         {
-            Ok(_) =>
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for FieldError
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result
+    {
+        write!(f, "{} {}", self.field, self.message)
+    }
+}
+
+#[derive(Debug)]
+pub enum Error
+{
+    Read{ path: PathBuf, error: std::io::Error },
+    Parse(Box<crate::ronx::ParseError>),
+    Write{ path: PathBuf, error: std::io::Error },
+    Migrate{ path: PathBuf, error: crate::migrate::Error },
+    Deserialize{ path: PathBuf, message: String },
+    Override(String),
+    Invalid{ path: PathBuf, errors: Vec<FieldError> },
+    // found is None when the tuning doesn't serialize to a struct with a valid version
+    Version{ path: PathBuf, expected: u32, found: Option<u32> },
+}
+
+impl Display for Error
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result
+    {
+        match self
+        {
+            Error::Read{ path, error } => write!(f, "Tuning -- could not read [{}]: {error}", path.display()),
+            Error::Parse(error) => write!(f, "Tuning -- could not parse {error}"),
+            Error::Write{ path, error } => write!(f, "Tuning -- could not write [{}]: {error}", path.display()),
+            Error::Migrate{ path, error } => write!(f, "Tuning -- could not upgrade [{}]: {error}", path.display()),
+            Error::Deserialize{ path, message } => write!(f, "Tuning -- could not deserialize [{}]: {message}", path.display()),
+            Error::Override(text) => write!(f, "Tuning -- invalid override [{text}], expected <tuning>.<field>=<value>"),
+            Error::Invalid{ path, errors } =>
             {
+                write!(f, "Tuning -- invalid [{}]: ", path.display())?;
+                for (index, error) in errors.iter().enumerate()
+                {
+                    let separator = if index == 0 { "" } else { ", " };
+                    write!(f, "{separator}{error}")?;
+                }
+                Ok(())
             },
-            Err(err) =>
+            Error::Version{ path, expected, found: Some(found) } => write!(f, "Tuning -- [{}] must be saved with version {expected}, not {found}", path.display()),
+            Error::Version{ path, expected, found: None } => write!(f, "Tuning -- [{}] must be saved with version {expected}, it has none", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+// the version and migrations of a tuning file come from Versioned
+pub trait Tuning: Versioned
+{
+    fn path() -> &'static std::path::Path;
+
+    // addresses the tuning in overrides, the file name of path by default
+    fn name() -> String
+    {
+        Self::path().file_stem().unwrap_or_default().to_string_lossy().into_owned()
+    }
+
+    // the per-user file layered over the asset, path below the platform config folder
+    fn user_path() -> Option<PathBuf>
+    {
+        crate::assets::user_config_dir().map(|dir| dir.join(Self::path()))
+    }
+
+    // checks the values once every layer is applied, reporting all the fields that are off
+    fn validate(&self) -> Result<(), Vec<FieldError>>
+    {
+        Ok(())
+    }
+
+    fn load() -> Result<Self, Error> where Self: Sized, for<'de> Self: serde::Deserialize<'de>
+    {
+        let tuning = crate::ronx::read_migrated_sync::<Self>(Self::path(), Self::migrations())
+            .map_err(|error| read_error(Self::path(), error))?;
+        validated(tuning)
+    }
+
+    // writes the defaults when the file is missing. existing files are upgraded and have their missing
+    // fields filled in through serde defaults in memory only, the file stays as the designer wrote it.
+    fn load_or_create() -> Result<Self, Error> where Self: Sized + Default + serde::Serialize, for<'de> Self: serde::Deserialize<'de>
+    {
+        validated(read_asset::<Self>()?.tuning)
+    }
+
+    // the explicit save of an outdated asset, rewrites it with the current version and every field.
+    // returns whether the file was written, formatting and comments of the old file are lost.
+    fn update_asset() -> Result<bool, Error> where Self: Sized + Default + serde::Serialize, for<'de> Self: serde::Deserialize<'de>
+    {
+        let read = read_asset::<Self>()?;
+        if read.outdated
+        {
+            read.tuning.save()?;
+        }
+        Ok(read.outdated)
+    }
+
+    // load_or_create, with the user file and then the overrides merged over it field by field.
+    // only a missing asset is ever written, the other layers are left as they are.
+    fn load_layered() -> Result<Layered<Self>, Error> where Self: Sized + Default + serde::Serialize, for<'de> Self: serde::Deserialize<'de>
+    {
+        let read = read_asset::<Self>()?;
+        let (tuning, sources) = layer::<Self>(read.value)?;
+        Ok(Layered{ tuning, sources, outdated: read.outdated })
+    }
+
+    // load_layered for an asset read elsewhere, such as by an asset loader on hot reload. its values are used
+    // as they are, the file is only read again for the fields it lists, the others keep Layer::Default.
+    // whether the file is outdated is decided once when the game starts, it's never reported here.
+    fn layer_over(asset: &Self) -> Result<Layered<Self>, Error> where Self: Sized + Default + serde::Serialize, for<'de> Self: serde::Deserialize<'de>
+    {
+        let listed = read_listed::<Self>()?;
+        let (tuning, sources) = layer::<Self>(to_value(asset).map(|value| listed_only(value, &listed)))?;
+        Ok(Layered{ tuning, sources, outdated: false })
+    }
+
+    // a file written with an older version would be migrated again on the next load
+    fn save(&self) -> Result<(), Error> where Self: Sized + serde::Serialize
+    {
+        let found = to_value(self).and_then(|value| crate::migrate::version(&value).ok());
+        if found != Some(Self::version())
+        {
+            return Err(Error::Version{ path: Self::path().into(), expected: Self::version(), found });
+        }
+
+        crate::ronx::write_sync(&self, Self::path())
+            .map_err(|error| Error::Write{ path: Self::path().into(), error })
+    }
+}
+
+// ronx reports syntax errors as io errors, they get their own variant here
+fn read_error(path: &std::path::Path, error: std::io::Error) -> Error
+{
+    match error.downcast::<crate::ronx::ParseError>()
+    {
+        Ok(parse_error) => Error::Parse(Box::new(parse_error)),
+        Err(error) => Error::Read{ path: path.into(), error },
+    }
+}
+
+fn validated<T: Tuning>(tuning: T) -> Result<T, Error>
+{
+    tuning.validate().map_err(|errors| Error::Invalid{ path: T::path().into(), errors })?;
+    Ok(tuning)
+}
+
+struct AssetRead<T>
+{
+    tuning: T,
+    // the migrated value, None when the file was just created
+    value: Option<Value>,
+    outdated: bool,
+}
+
+fn read_asset<T>() -> Result<AssetRead<T>, Error>
+where
+    for<'de> T: Tuning + Default + serde::Serialize + serde::Deserialize<'de>,
+{
+    let (value, included) = match crate::ronx::read_value_sync(T::path())
+    {
+        Ok(read) => read,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound =>
+        {
+            let tuning = T::default();
+            save_in_place(&tuning);
+            return Ok(AssetRead{ tuning, value: None, outdated: false });
+        },
+        Err(error) => return Err(read_error(T::path(), error)),
+    };
+
+    // on errors the file is left as it is, it holds hand edits that only need fixing
+    let migrated = crate::migrate::migrate(value.clone(), T::migrations())
+        .map_err(|error| Error::Migrate{ path: T::path().into(), error })?;
+    let tuning = migrated.clone().into_rust::<T>()
+        .map_err(|error| Error::Deserialize{ path: T::path().into(), message: error.to_string() })?;
+
+    // files with includes are never outdated, writing them back would copy the included fields into them
+    let outdated = !included && outdated(&tuning, &value);
+    Ok(AssetRead{ tuning, value: Some(migrated), outdated })
+}
+
+// the migrated value of the asset file with its includes merged in, for the fields it lists
+fn read_listed<T: Tuning>() -> Result<Value, Error>
+{
+    let (value, _) = crate::ronx::read_value_sync(T::path()).map_err(|error| read_error(T::path(), error))?;
+    crate::migrate::migrate(value, T::migrations())
+        .map_err(|error| Error::Migrate{ path: T::path().into(), error })
+}
+
+// the fields of value that listed has too, nested structs field by field. the others came from serde defaults.
+fn listed_only(value: Value, listed: &Value) -> Value
+{
+    let Value::Map(listed_map) = listed else
+    {
+        return value;
+    };
+    let Value::Map(map) = value else
+    {
+        return value;
+    };
+
+    Value::Map(map.into_iter()
+        .filter_map(|(key, field)|
+        {
+            let (_, listed_field) = listed_map.iter().find(|(listed_key, _)| **listed_key == key)?;
+            Some((key, listed_only(field, listed_field)))
+        })
+        .collect())
+}
+
+// upgraded and merged files differ from what's on disk. compared through Ord, the PartialEq of ron maps
+// stops at the end of the shorter map and misses added fields.
+fn outdated<T: serde::Serialize>(tuning: &T, on_disk: &Value) -> bool
+{
+    to_value(tuning).is_some_and(|current| current.cmp(on_disk) != std::cmp::Ordering::Equal)
+}
+
+// the asset folder can be read only, the values created are still good to use then
+fn save_in_place<T: Tuning + serde::Serialize>(tuning: &T)
+{
+    if let Err(err) = tuning.save()
+    {
+        eprintln!("{} -- {}", debug_name!(), err);
+    }
+}
+
+// the defaults, the asset value, the user file and the overrides for T, merged in that order
+fn layer<T>(asset_value: Option<Value>) -> Result<(T, Sources), Error>
+where
+    for<'de> T: Tuning + Default + serde::Serialize + serde::Deserialize<'de>,
+{
+    let mut value = Value::Map(Default::default());
+    let mut sources = Sources::new();
+    if let Some(defaults) = to_value(&T::default())
+    {
+        merge_layer(&mut value, defaults, Layer::Default, &mut sources);
+    }
+    if let Some(asset_value) = asset_value
+    {
+        merge_layer(&mut value, asset_value, Layer::Asset, &mut sources);
+    }
+
+    if let Some(user_value) = read_user::<T>()?
+    {
+        merge_layer(&mut value, user_value, Layer::User, &mut sources);
+    }
+
+    // overrides for other tunings are theirs to report, a typo in one doesn't stop the others from loading
+    let name = T::name();
+    for text in override_texts().into_iter().filter(|text| override_target(text) == name)
+    {
+        let Some((_, fields, field_value)) = parse_override(&text) else
+        {
+            return Err(Error::Override(text));
+        };
+        merge_layer(&mut value, nest(&fields, field_value), Layer::Override, &mut sources);
+    }
+
+    let tuning = value.into_rust::<T>()
+        .map_err(|error| Error::Deserialize{ path: T::path().into(), message: error.to_string() })?;
+    Ok((validated(tuning)?, sources))
+}
+
+// user files are optional and usually only list a few fields
+fn read_user<T: Tuning>() -> Result<Option<Value>, Error>
+{
+    let Some(path) = T::user_path() else
+    {
+        return Ok(None);
+    };
+
+    let text = match std::fs::read_to_string(&path)
+    {
+        Ok(text) => text,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(Error::Read{ path, error }),
+    };
+    let value = ron::from_str(&text)
+        .map_err(|error| Error::Parse(Box::new(crate::ronx::ParseError::new(&path, &text, error))))?;
+    crate::migrate::migrate(value, T::migrations())
+        .map(Some)
+        .map_err(|error| Error::Migrate{ path, error })
+}
+
+fn to_value<T: serde::Serialize>(data: &T) -> Option<Value>
+{
+    let text = ron::to_string(data).ok()?;
+    ron::from_str(&text).ok()
+}
+
+// ronx::merge, recording every leaf field written in sources with layer
+pub fn merge_layer(into: &mut Value, from: Value, layer: Layer, sources: &mut Sources)
+{
+    crate::ronx::merge(into, from, Some(&mut |path: &str, field: &Value| record_sources(field, layer, path, sources)));
+}
+
+fn record_sources(value: &Value, layer: Layer, path: &str, sources: &mut Sources)
+{
+    let nested = format!("{path}.");
+    sources.retain(|field, _| field != path && !field.starts_with(&nested));
+    match value
+    {
+        Value::Map(map) if !map.is_empty() =>
+        {
+            for (key, field) in map.iter()
             {
-                eprintln!("{} -- Failed to write [{}] with error [{}]", debug_name!(), Self::path().display(), err);
-                debug_assert!(false, "Failed to write {}", Self::path().display());
+                record_sources(field, layer, &crate::ronx::join_path(path, key), sources);
             }
+        },
+        _ =>
+        {
+            sources.insert(path.to_owned(), layer);
+        }
+    }
+}
+
+// wraps value in one struct per field, so it can be merged at that path
+fn nest(fields: &[String], value: Value) -> Value
+{
+    fields.iter().rev().fold(value, |value, field|
+    {
+        Value::Map([(Value::String(field.clone()), value)].into_iter().collect())
+    })
+}
+
+// the tuning an override is meant for, "board_vis" for "board_vis.cell_size=32,32"
+fn override_target(text: &str) -> &str
+{
+    let end = text.find(['.', '=']).unwrap_or(text.len());
+    text[..end].trim()
+}
+
+// "board_vis.cell_size=32,32" to the tuning name, the field path and the value.
+// the value is RON, the parentheses around a tuple can be left out.
+pub fn parse_override(text: &str) -> Option<(String, Vec<String>, Value)>
+{
+    let (key, value) = text.split_once('=')?;
+    let mut path = key.trim().split('.').map(str::to_owned);
+    let name = path.next().filter(|name| !name.is_empty())?;
+    let fields = path.collect::<Vec<_>>();
+    if fields.is_empty() || fields.iter().any(String::is_empty)
+    {
+        return None;
+    }
+
+    let value = ron::from_str(value)
+        .or_else(|_| ron::from_str(&format!("({value})")))
+        .ok()?;
+    Some((name, fields, value))
+}
+
+// the overrides from the environment, then the ones from the command line
+fn override_texts() -> Vec<String>
+{
+    let mut texts = std::env::vars_os()
+        .filter_map(|(key, value)|
+        {
+            let key = key.to_str()?.strip_prefix(OVERRIDE_ENV_PREFIX)?.to_owned();
+            Some(format!("{key}={}", value.to_str()?))
+        })
+        .collect::<Vec<_>>();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next()
+    {
+        if arg == OVERRIDE_ARG
+        {
+            texts.extend(args.next());
+        }
+        else if let Some(text) = arg.strip_prefix(OVERRIDE_ARG).and_then(|arg| arg.strip_prefix('='))
+        {
+            texts.push(text.to_owned());
+        }
+    }
+    texts
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[derive(Debug, PartialEq, serde::Deserialize)]
+    struct Grid
+    {
+        world_line_width: f32,
+        uv_width: f32,
+    }
+
+    #[derive(Debug, PartialEq, serde::Deserialize)]
+    struct Board
+    {
+        cell_size: (f32, f32),
+        grid: Grid,
+    }
+
+    fn parse(text: &str) -> Value
+    {
+        ron::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn test_merge_layers()
+    {
+        let mut value = Value::Map(Default::default());
+        let mut sources = Sources::new();
+        merge_layer(&mut value, parse("(cell_size: (28.0, 28.0), grid: (world_line_width: 1.0, uv_width: 0.5))"), Layer::Default, &mut sources);
+        merge_layer(&mut value, parse("(grid: (uv_width: 0.25))"), Layer::Asset, &mut sources);
+        merge_layer(&mut value, parse("(grid: (world_line_width: 2.0))"), Layer::User, &mut sources);
+        let (_, fields, field_value) = parse_override("board_vis.cell_size=32,32").unwrap();
+        merge_layer(&mut value, nest(&fields, field_value), Layer::Override, &mut sources);
+
+        assert_eq!(value.into_rust::<Board>().unwrap(), Board
+        {
+            cell_size: (32.0, 32.0),
+            grid: Grid{ world_line_width: 2.0, uv_width: 0.25 },
+        });
+        assert_eq!(sources.into_iter().collect::<Vec<_>>(), vec![
+            ("cell_size".to_owned(), Layer::Override),
+            ("grid.uv_width".to_owned(), Layer::Asset),
+            ("grid.world_line_width".to_owned(), Layer::User),
+        ]);
+    }
+
+    #[test]
+    fn test_listed_only_sources()
+    {
+        // a hot reloaded asset has every field, the ones its file leaves out still come from the defaults
+        let asset = parse("(cell_size: (28.0, 28.0), grid: (world_line_width: 1.0, uv_width: 0.25))");
+        let mut value = Value::Map(Default::default());
+        let mut sources = Sources::new();
+        merge_layer(&mut value, parse("(cell_size: (28.0, 28.0), grid: (world_line_width: 1.0, uv_width: 0.5))"), Layer::Default, &mut sources);
+        merge_layer(&mut value, listed_only(asset, &parse("(grid: (uv_width: 0.25))")), Layer::Asset, &mut sources);
+
+        // the PartialEq of ron maps misses extra fields, see outdated
+        assert_eq!(value.cmp(&parse("(cell_size: (28.0, 28.0), grid: (world_line_width: 1.0, uv_width: 0.25))")), std::cmp::Ordering::Equal);
+        assert_eq!(sources.into_iter().collect::<Vec<_>>(), vec![
+            ("cell_size".to_owned(), Layer::Default),
+            ("grid.uv_width".to_owned(), Layer::Asset),
+            ("grid.world_line_width".to_owned(), Layer::Default),
+        ]);
+    }
+
+    #[test]
+    fn test_merge_replaces_nested_sources()
+    {
+        let mut value = Value::Map(Default::default());
+        let mut sources = Sources::new();
+        merge_layer(&mut value, parse("(grid: (world_line_width: 1.0, uv_width: 0.5))"), Layer::Default, &mut sources);
+        merge_layer(&mut value, parse("(grid: None)"), Layer::Override, &mut sources);
+        assert_eq!(sources.into_iter().collect::<Vec<_>>(), vec![("grid".to_owned(), Layer::Override)]);
+    }
+
+    #[derive(Default, serde::Deserialize)]
+    struct Spacing
+    {
+        gaps: Vec<f32>,
+    }
+
+    impl Versioned for Spacing {}
+
+    impl Tuning for Spacing
+    {
+        fn path() -> &'static std::path::Path
+        {
+            std::path::Path::new("tuning/spacing.ron")
+        }
+
+        fn validate(&self) -> Result<(), Vec<FieldError>>
+        {
+            let errors = self.gaps.iter()
+                .enumerate()
+                .filter(|(_, gap)| **gap < 0.0)
+                .map(|(index, _)| FieldError::new(format!("gaps[{index}]"), "must not be negative"))
+                .collect::<Vec<_>>();
+            if errors.is_empty() { Ok(()) } else { Err(errors) }
+        }
+    }
+
+    #[test]
+    fn test_validate()
+    {
+        assert!(validated(Spacing{ gaps: vec![1.0, 0.0] }).is_ok());
+
+        let err = validated(Spacing{ gaps: vec![-1.0, 2.0, -0.5] }).err().unwrap();
+        let Error::Invalid{ errors, .. } = &err else
+        {
+            panic!("expected Error::Invalid, got {err}");
+        };
+        assert_eq!(errors.iter().map(|error| error.field.as_str()).collect::<Vec<_>>(), vec!["gaps[0]", "gaps[2]"]);
+        assert_eq!(err.to_string(), "Tuning -- invalid [tuning/spacing.ron]: gaps[0] must not be negative, gaps[2] must not be negative");
+    }
+
+    #[derive(serde::Serialize)]
+    struct Gaps
+    {
+        version: u32,
+        gaps: Vec<f32>,
+    }
+
+    impl Versioned for Gaps
+    {
+        fn migrations() -> &'static [crate::migrate::Migration]
+        {
+            &[crate::migrate::add_version]
         }
     }
+
+    impl Tuning for Gaps
+    {
+        fn path() -> &'static std::path::Path
+        {
+            std::path::Path::new("tuning/gaps.ron")
+        }
+    }
+
+    #[test]
+    fn test_save_version()
+    {
+        // refused before anything is written
+        let err = Gaps{ version: 0, gaps: vec![] }.save().err().unwrap();
+        assert!(matches!(err, Error::Version{ expected: 1, found: Some(0), .. }), "{err}");
+        assert_eq!(err.to_string(), "Tuning -- [tuning/gaps.ron] must be saved with version 1, not 0");
+    }
+
+    #[test]
+    fn test_outdated()
+    {
+        let gaps = Gaps{ version: 1, gaps: vec![1.0] };
+        assert!(!outdated(&gaps, &parse("(version: 1, gaps: [1.0])")));
+        assert!(outdated(&gaps, &parse("(gaps: [1.0])")));
+        assert!(outdated(&gaps, &parse("(version: 0, gaps: [1.0])")));
+    }
+
+    #[test]
+    fn test_override_target()
+    {
+        assert_eq!(override_target("board_vis.cell_size=32,32"), "board_vis");
+        assert_eq!(override_target("stats=1"), "stats");
+        assert_eq!(override_target(" board_vis .cell_size"), "board_vis");
+        assert_eq!(override_target("board_vis"), "board_vis");
+    }
+
+    #[test]
+    fn test_parse_override()
+    {
+        assert_eq!(parse_override("board_vis.grid.uv_width=0.25"),
+            Some(("board_vis".to_owned(), vec!["grid".to_owned(), "uv_width".to_owned()], parse("0.25"))));
+        assert_eq!(parse_override("board_vis.cell_size=(32, 32)").map(|o| o.2), Some(parse("(32, 32)")));
+        assert_eq!(parse_override("board_vis.cell_size"), None);
+        assert_eq!(parse_override("board_vis=1"), None);
+        assert_eq!(parse_override("board_vis..uv_width=1"), None);
+        assert_eq!(parse_override("board_vis.cell_size=(32"), None);
+    }
 }
@@ -0,0 +1,93 @@
+use bevy::asset::{Asset, AssetServer, Handle, LoadContext};
+use std::path::{Path, PathBuf};
+
+// where the handles of dependencies come from: the load context while an asset loads, so the asset waits
+// for them and reloads with them, or the asset server for values read outside of the asset loaders
+pub trait DependencyLoader
+{
+    fn load_dependency<T: Asset>(&mut self, path: &Path) -> Handle<T>;
+}
+
+impl DependencyLoader for LoadContext<'_>
+{
+    fn load_dependency<T: Asset>(&mut self, path: &Path) -> Handle<T>
+    {
+        self.load(path)
+    }
+}
+
+impl DependencyLoader for AssetServer
+{
+    fn load_dependency<T: Asset>(&mut self, path: &Path) -> Handle<T>
+    {
+        self.load(path)
+    }
+}
+
+// assets holding AssetRef fields, load_dependencies loads every one of them
+pub trait LoadDependencies
+{
+    fn load_dependencies(&mut self, _loader: &mut impl DependencyLoader)
+    {
+    }
+}
+
+// a handle written as its asset path in RON files. the handle is the default one until load is called.
+pub struct AssetRef<T: Asset>
+{
+    pub path: PathBuf,
+    pub handle: Handle<T>,
+}
+
+impl<T: Asset> AssetRef<T>
+{
+    pub fn new(path: impl Into<PathBuf>) -> Self
+    {
+        Self
+        {
+            path: path.into(),
+            handle: Handle::default(),
+        }
+    }
+
+    pub fn load(&mut self, loader: &mut impl DependencyLoader)
+    {
+        self.handle = loader.load_dependency(&self.path);
+    }
+}
+
+impl<T: Asset> Clone for AssetRef<T>
+{
+    fn clone(&self) -> Self
+    {
+        Self
+        {
+            path: self.path.clone(),
+            handle: self.handle.clone(),
+        }
+    }
+}
+
+impl<T: Asset> std::fmt::Debug for AssetRef<T>
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        f.debug_struct("AssetRef").field("path", &self.path).field("handle", &self.handle.id()).finish()
+    }
+}
+
+impl<T: Asset> serde::Serialize for AssetRef<T>
+{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    {
+        self.path.serialize(serializer)
+    }
+}
+
+impl<'de, T: Asset> serde::Deserialize<'de> for AssetRef<T>
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>
+    {
+        PathBuf::deserialize(deserializer).map(Self::new)
+    }
+}
@@ -1,3 +1,3 @@
 
-pub const ASSET_ROOT_ENV: &str = "BEVY_ASSET_ROOT";
+pub use base::assets::ASSET_ROOT_ENV;
 
@@ -1,6 +1,9 @@
 
+pub mod asset_ref;
 pub mod ron;
 pub mod helper;
+pub mod loading;
+pub mod tuning;
 
 pub fn hello_bevyx()
 {
@@ -0,0 +1,206 @@
+use bevy::asset::RecursiveDependencyLoadState;
+use bevy::asset::UntypedHandle;
+use bevy::prelude::*;
+use bevy::state::state::FreelyMutableState;
+use std::collections::BTreeMap;
+
+// a resource of handles the game can't start without, loaded in PreStartup so Startup systems can use it
+pub trait AssetCollection: Resource
+{
+    fn load(asset_server: &AssetServer) -> Self;
+    fn handles(&self) -> Vec<UntypedHandle>;
+}
+
+// the PreStartup systems loading the asset collections
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CollectionLoadSet;
+
+// every handle the loading state waits for, each one along with all of its dependencies
+#[derive(Resource, Default)]
+pub struct LoadingAssets
+{
+    handles: Vec<UntypedHandle>,
+}
+
+impl LoadingAssets
+{
+    pub fn add(&mut self, handle: impl Into<UntypedHandle>)
+    {
+        self.handles.push(handle.into());
+    }
+}
+
+// the load error of every waited for asset that failed, by asset path
+#[derive(Resource, Default)]
+pub struct LoadingErrors
+{
+    pub errors: BTreeMap<String, String>,
+}
+
+pub trait LoadingAppExt
+{
+    fn init_collection<C: AssetCollection>(&mut self) -> &mut Self;
+}
+
+impl LoadingAppExt for App
+{
+    fn init_collection<C: AssetCollection>(&mut self) -> &mut Self
+    {
+        self
+            .init_resource::<LoadingAssets>()
+            .add_systems(PreStartup, load_collection::<C>.in_set(CollectionLoadSet))
+    }
+}
+
+fn load_collection<C: AssetCollection>
+(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut loading: ResMut<LoadingAssets>,
+)
+{
+    let collection = C::load(&asset_server);
+    for handle in collection.handles()
+    {
+        loading.add(handle);
+    }
+    commands.insert_resource(collection);
+}
+
+// holds the app in the loading state until everything in LoadingAssets has loaded, then moves on to the next state.
+// when an asset fails the app stays in loading and lists the errors, a fix picked up by the file watcher still lets it through.
+pub struct LoadingStatePlugin<S>
+{
+    pub loading: S,
+    pub next: S,
+}
+
+#[derive(Resource)]
+struct NextAfterLoading<S>(S);
+
+#[derive(Component)]
+struct LoadingErrorScreen;
+
+impl<S: FreelyMutableState> Plugin for LoadingStatePlugin<S>
+{
+    fn build(&self, app: &mut App)
+    {
+        app
+            .init_resource::<LoadingAssets>()
+            .init_resource::<LoadingErrors>()
+            .insert_resource(NextAfterLoading(self.next.clone()))
+            .add_systems(Update, (check_loading::<S>, show_loading_errors).chain().run_if(in_state(self.loading.clone())))
+            .add_systems(OnExit(self.loading.clone()), despawn_loading_errors);
+    }
+}
+
+fn check_loading<S: FreelyMutableState>
+(
+    asset_server: Res<AssetServer>,
+    loading: Res<LoadingAssets>,
+    next: Res<NextAfterLoading<S>>,
+    mut errors: ResMut<LoadingErrors>,
+    mut next_state: ResMut<NextState<S>>,
+)
+{
+    let mut loaded = true;
+    let mut failed = BTreeMap::new();
+    for handle in &loading.handles
+    {
+        match asset_server.recursive_dependency_load_state(handle.id())
+        {
+            RecursiveDependencyLoadState::Loaded => {},
+            RecursiveDependencyLoadState::Failed(error) =>
+            {
+                let path = handle.path().map_or_else(|| format!("{:?}", handle.id()), |path| path.to_string());
+                failed.insert(path, error.to_string());
+            },
+            RecursiveDependencyLoadState::NotLoaded | RecursiveDependencyLoadState::Loading => loaded = false,
+        }
+    }
+
+    if errors.errors != failed
+    {
+        errors.errors = failed;
+    }
+    if loaded && errors.errors.is_empty()
+    {
+        next_state.set(next.0.clone());
+    }
+}
+
+fn show_loading_errors
+(
+    mut commands: Commands,
+    errors: Res<LoadingErrors>,
+    screen_query: Query<Entity, With<LoadingErrorScreen>>,
+)
+{
+    if !errors.is_changed()
+    {
+        return;
+    }
+    for entity in &screen_query
+    {
+        commands.entity(entity).despawn_recursive();
+    }
+    if errors.errors.is_empty()
+    {
+        return;
+    }
+
+    commands
+        .spawn
+        ((
+            LoadingErrorScreen,
+            Node
+            {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.9)),
+            GlobalZIndex(i32::MAX),
+        ))
+        .with_children(|parent|
+        {
+            parent.spawn
+            ((
+                Text::new("Could not load the game"),
+                TextFont
+                {
+                    font_size: 32.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(1.0, 0.3, 0.3)),
+            ));
+            for (path, error) in &errors.errors
+            {
+                parent.spawn
+                ((
+                    Text::new(format!("[{path}] {error}")),
+                    TextFont
+                    {
+                        font_size: 16.0,
+                        ..default()
+                    },
+                ));
+            }
+        });
+}
+
+fn despawn_loading_errors
+(
+    mut commands: Commands,
+    screen_query: Query<Entity, With<LoadingErrorScreen>>,
+)
+{
+    for entity in &screen_query
+    {
+        commands.entity(entity).despawn_recursive();
+    }
+}
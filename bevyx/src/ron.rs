@@ -1,10 +1,26 @@
-use bevy::app::{App, Plugin};
-use bevy::asset::io::Reader;
-use bevy::asset::{Asset, AssetApp, AssetLoader, LoadContext};
-use ron::de::from_bytes;
+use crate::asset_ref::LoadDependencies;
+
+use base::debug_name;
+use base::migrate::Versioned;
+use base::ronx::{from_value, include_files, IncludeError, IncludeReader, ParseError, ValueError};
+use bevy::app::{App, Plugin, PreUpdate};
+use bevy::asset::io::{AssetSourceId, AssetWriterError, MissingAssetSourceError, MissingAssetWriterError, Reader, Writer};
+use bevy::asset::processor::LoadTransformAndSave;
+use bevy::asset::saver::{AssetSaver, SavedAsset};
+use bevy::asset::transformer::IdentityAssetTransformer;
+use bevy::asset::{Asset, AssetApp, AssetEvent, AssetLoadFailedEvent, AssetLoader, AssetPath, AssetServer, AsyncWriteExt, ErasedLoadedAsset, LoadContext, LoadedAsset, ReadAssetBytesError};
+use bevy::ecs::event::EventReader;
+use bevy::ecs::system::{Res, ResMut, Resource};
+use bevy::tasks::{IoTaskPool, Task};
+use ron::Value;
+use std::collections::BTreeMap;
 use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use thiserror::Error;
 
+pub type RonAssetProcessor<A> = LoadTransformAndSave<RonAssetLoader<A>, IdentityAssetTransformer<A>, RonAssetSaver<A>>;
+
 pub struct RonAssetPlugin<A>
 {
     extensions: &'static [&'static str],
@@ -13,16 +29,72 @@ pub struct RonAssetPlugin<A>
 
 impl<A> Plugin for RonAssetPlugin<A>
 where
-    for<'de> A: serde::Deserialize<'de> + Asset,
+    for<'de> A: serde::Deserialize<'de> + serde::Serialize + Asset + Versioned + LoadDependencies,
 {
     fn build(&self, app: &mut App)
     {
+        // the processor only exists when the asset plugin runs in processed mode, this is a no-op otherwise.
+        // it isn't made the default for the extensions, as every RON asset type shares them.
+        app.init_resource::<RonLoadErrors>();
+        let queued = app.world().resource::<RonLoadErrors>().queued.clone();
         app.init_asset::<A>()
             .register_asset_loader(RonAssetLoader::<A>
             {
                 extensions: self.extensions,
+                queued,
                 _marker: PhantomData,
-            });
+            })
+            .register_asset_processor::<RonAssetProcessor<A>>(RonAssetSaver::<A>::default().into())
+            .add_systems(PreUpdate, track_load_errors::<A>);
+    }
+}
+
+// the loaders run without the world, they queue the warning of every load by asset path, None when it had none
+type LoadWarnings = Arc<Mutex<Vec<(String, Option<String>)>>>;
+
+// the last load error of every RON asset that is currently broken, by asset path
+#[derive(Resource, Default)]
+pub struct RonLoadErrors
+{
+    pub errors: BTreeMap<String, String>,
+    // assets that loaded with problems, such as fields they ignore outside of strict mode
+    pub warnings: BTreeMap<String, String>,
+    queued: LoadWarnings,
+}
+
+fn track_load_errors<A: Asset>
+(
+    mut failed_events: EventReader<AssetLoadFailedEvent<A>>,
+    mut asset_events: EventReader<AssetEvent<A>>,
+    asset_server: Res<AssetServer>,
+    mut load_errors: ResMut<RonLoadErrors>,
+)
+{
+    for event in asset_events.read()
+    {
+        let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = event else
+        {
+            continue;
+        };
+        if let Some(path) = asset_server.get_path(*id)
+        {
+            load_errors.errors.remove(&path.to_string());
+        }
+    }
+    for event in failed_events.read()
+    {
+        load_errors.warnings.remove(&event.path.to_string());
+        load_errors.errors.insert(event.path.to_string(), event.error.to_string());
+    }
+
+    let queued = std::mem::take(&mut *load_errors.queued.lock().unwrap());
+    for (path, warning) in queued
+    {
+        match warning
+        {
+            Some(warning) => load_errors.warnings.insert(path, warning),
+            None => load_errors.warnings.remove(&path),
+        };
     }
 }
 
@@ -40,7 +112,7 @@ impl<A> Default for RonAssetPlugin<A>
 
 impl<A> RonAssetPlugin<A>
 where
-    for<'de> A: serde::Deserialize<'de> + Asset,
+    for<'de> A: serde::Deserialize<'de> + serde::Serialize + Asset + Versioned + LoadDependencies,
 {
     pub fn new(extensions: &'static [&'static str]) -> Self
     {
@@ -51,9 +123,19 @@ where
     }
 }
 
+#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
+pub struct RonLoaderSettings
+{
+    // fails on fields the asset type doesn't know about, instead of ignoring them like serde does.
+    // fields the type skips when serializing count as unknown.
+    pub strict: bool,
+}
+
 pub struct RonAssetLoader<A>
 {
     extensions: &'static [&'static str],
+    // shared with RonLoadErrors
+    queued: LoadWarnings,
     _marker: PhantomData<A>,
 }
 
@@ -64,32 +146,457 @@ pub enum RonLoaderError
     #[error("Could not read the file: {0}")]
     Io(#[from] std::io::Error),
     #[error("Could not parse RON: {0}")]
-    RonError(#[from] ron::error::SpannedError),
+    RonError(#[from] ParseError),
+    #[error("Could not deserialize RON: {0}")]
+    DeserializeError(#[from] ron::Error),
+    #[error("Could not deserialize RON: {0}")]
+    ValueError(#[from] ValueError),
+    #[error("Unknown fields: {}", .0.join(", "))]
+    UnknownFields(Vec<String>),
+    #[error("{0}")]
+    IncludeError(#[from] IncludeError),
+    #[error("Could not read an included file: {0}")]
+    ReadIncludeError(#[from] ReadAssetBytesError),
+    #[error("{0}")]
+    MigrateError(#[from] base::migrate::Error),
 }
 
 impl<A> AssetLoader for RonAssetLoader<A>
 where
-    for<'de> A: serde::Deserialize<'de> + Asset,
+    for<'de> A: serde::Deserialize<'de> + serde::Serialize + Asset + Versioned + LoadDependencies,
 {
     type Asset = A;
-    type Settings = ();
+    type Settings = RonLoaderSettings;
     type Error = RonLoaderError;
 
     async fn load(
         &self,
         reader: &mut dyn Reader,
-        _settings: &(),
-        _load_context: &mut LoadContext<'_>,
+        settings: &RonLoaderSettings,
+        load_context: &mut LoadContext<'_>,
     ) -> Result<Self::Asset, Self::Error>
     {
         let mut bytes = Vec::new();
         reader.read_to_end(&mut bytes).await?;
-        let asset = from_bytes::<A>(&bytes)?;
+        let text = String::from_utf8_lossy(&bytes).into_owned();
+        let path = load_context.path().to_path_buf();
+        let parse_error = |error| ParseError::new(&path, &text, error);
+
+        let value = ron::from_str::<Value>(&text).map_err(parse_error)?;
+        let (value, included) = include_files(&mut ContextReader(load_context), &path, value).await?;
+        // unversioned types have no version field for migrate to stamp
+        let migrated = if A::migrations().is_empty()
+        {
+            value.clone()
+        }
+        else
+        {
+            base::migrate::migrate(value.clone(), A::migrations())?
+        };
+
+        // files needing no includes or upgrades are deserialized from the text directly, so type errors keep their position
+        let unchanged = !included && migrated.cmp(&value) == std::cmp::Ordering::Equal;
+        let value = migrated;
+        let mut asset = if unchanged
+        {
+            ron::from_str::<A>(&text).map_err(parse_error)?
+        }
+        else
+        {
+            from_value::<A>(&path, value.clone())?
+        };
+
+        // serde drops fields it doesn't know, mostly typos and fields the code no longer has
+        let unknown = unknown_fields_of(&value, &asset)?;
+        let warning = if unknown.is_empty()
+        {
+            None
+        }
+        else if settings.strict
+        {
+            return Err(RonLoaderError::UnknownFields(unknown));
+        }
+        else
+        {
+            eprintln!("{} -- [{}] ignores unknown fields: {}", debug_name!(), path.display(), unknown.join(", "));
+            Some(format!("Ignores unknown fields: {}", unknown.join(", ")))
+        };
+        self.queued.lock().unwrap().push((load_context.asset_path().to_string(), warning));
+
+        asset.load_dependencies(load_context);
         Ok(asset)
     }
 
     fn extensions(&self) -> &[&str]
     {
-        &self.extensions
+        self.extensions
+    }
+}
+
+// reads included files through the load context, so editing one reloads every asset including it
+struct ContextReader<'a, 'ctx>(&'a mut LoadContext<'ctx>);
+
+impl IncludeReader for ContextReader<'_, '_>
+{
+    type Error = RonLoaderError;
+
+    async fn read_include(&mut self, file_path: &Path) -> Result<Value, RonLoaderError>
+    {
+        let source = self.0.asset_path().source().clone_owned();
+        let bytes = self.0.read_asset_bytes(AssetPath::from(file_path.to_path_buf()).with_source(source)).await?;
+        let text = String::from_utf8_lossy(&bytes).into_owned();
+        Ok(ron::from_str::<Value>(&text).map_err(|error| ParseError::new(file_path, &text, error))?)
+    }
+}
+
+// the paths of the struct fields in value that asset doesn't write back
+fn unknown_fields_of<A: serde::Serialize>(value: &Value, asset: &A) -> ron::Result<Vec<String>>
+{
+    let known = ron::from_str::<Value>(&ron::to_string(asset)?).map_err(|error| error.code)?;
+    let mut unknown = vec![];
+    unknown_fields(value, &known, "", &mut unknown);
+    Ok(unknown)
+}
+
+// the paths of the struct fields in value that known doesn't have
+fn unknown_fields(value: &Value, known: &Value, path: &str, unknown: &mut Vec<String>)
+{
+    match (value, known)
+    {
+        (Value::Map(map), Value::Map(known_map)) =>
+        {
+            for (key, field) in map.iter()
+            {
+                let name = match key
+                {
+                    Value::String(name) => name.clone(),
+                    key => ron::to_string(key).unwrap_or_default(),
+                };
+                let field_path = if path.is_empty() { name } else { format!("{path}.{name}") };
+                match known_map.iter().find(|(known_key, _)| *known_key == key)
+                {
+                    Some((_, known_field)) => unknown_fields(field, known_field, &field_path, unknown),
+                    None => unknown.push(field_path),
+                }
+            }
+        },
+        (Value::Seq(seq), Value::Seq(known_seq)) =>
+        {
+            for (index, (element, known_element)) in seq.iter().zip(known_seq).enumerate()
+            {
+                unknown_fields(element, known_element, &format!("{path}[{index}]"), unknown);
+            }
+        },
+        (Value::Option(Some(inner)), Value::Option(Some(known_inner))) =>
+        {
+            unknown_fields(inner, known_inner, path, unknown);
+        },
+        _ => {},
+    }
+}
+
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct RonSaverSettings
+{
+    // one field per line like the files written by base::ronx, everything on one line otherwise
+    pub pretty: bool,
+}
+
+impl Default for RonSaverSettings
+{
+    fn default() -> Self
+    {
+        Self
+        {
+            pretty: true,
+        }
+    }
+}
+
+pub struct RonAssetSaver<A>
+{
+    _marker: PhantomData<A>,
+}
+
+impl<A> Default for RonAssetSaver<A>
+{
+    fn default() -> Self
+    {
+        Self
+        {
+            _marker: PhantomData,
+        }
+    }
+}
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum RonSaverError
+{
+    #[error("Could not write the file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not serialize RON: {0}")]
+    RonError(#[from] ron::Error),
+    #[error("Could not open the asset writer: {0}")]
+    WriterError(#[from] AssetWriterError),
+    #[error("{0}")]
+    MissingSource(#[from] MissingAssetSourceError),
+    #[error("{0}")]
+    MissingWriter(#[from] MissingAssetWriterError),
+}
+
+impl<A> AssetSaver for RonAssetSaver<A>
+where
+    for<'de> A: serde::Deserialize<'de> + serde::Serialize + Asset + Versioned + LoadDependencies,
+{
+    type Asset = A;
+    type Settings = RonSaverSettings;
+    type OutputLoader = RonAssetLoader<A>;
+    type Error = RonSaverError;
+
+    async fn save(
+        &self,
+        writer: &mut Writer,
+        asset: SavedAsset<'_, A>,
+        settings: &RonSaverSettings,
+    ) -> Result<RonLoaderSettings, Self::Error>
+    {
+        let text = if settings.pretty
+        {
+            ron::ser::to_string_pretty(&*asset, ron::ser::PrettyConfig::default())?
+        }
+        else
+        {
+            ron::to_string(&*asset)?
+        };
+        writer.write_all(text.as_bytes()).await?;
+        Ok(RonLoaderSettings::default())
+    }
+}
+
+// writes asset to path in the default asset source with RonAssetSaver, for tools editing assets at runtime.
+// the file watcher then reloads it like any other change.
+pub fn save_ron_asset<A>(asset_server: &AssetServer, path: impl Into<PathBuf>, asset: A) -> Task<Result<(), RonSaverError>>
+where
+    for<'de> A: serde::Deserialize<'de> + serde::Serialize + Asset + Versioned + LoadDependencies,
+{
+    let asset_server = asset_server.clone();
+    let path = path.into();
+    IoTaskPool::get().spawn(async move
+    {
+        let source = asset_server.get_source(AssetSourceId::Default)?;
+        let mut writer = source.writer()?.write(&path).await?;
+        let loaded: ErasedLoadedAsset = LoadedAsset::from(asset).into();
+        let saved = SavedAsset::<A>::from_loaded(&loaded).expect("save_ron_asset -- the erased asset is always an A");
+        RonAssetSaver::<A>::default().save(&mut *writer, saved, &RonSaverSettings::default()).await?;
+        writer.flush().await?;
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::asset_ref::{AssetRef, DependencyLoader};
+    use bevy::asset::{AssetPlugin, LoadState, RecursiveDependencyLoadState};
+    use bevy::prelude::*;
+
+    #[derive(Asset, TypePath, Default, serde::Deserialize, serde::Serialize)]
+    #[serde(default)]
+    struct Level
+    {
+        name: String,
+        size: u32,
+        next: Option<AssetRef<Level>>,
+    }
+
+    impl Versioned for Level {}
+
+    impl LoadDependencies for Level
+    {
+        fn load_dependencies(&mut self, loader: &mut impl DependencyLoader)
+        {
+            if let Some(next) = &mut self.next
+            {
+                next.load(loader);
+            }
+        }
+    }
+
+    // a folder of its own per test, filled with files by relative path
+    fn test_folder(name: &str, files: &[(&str, &str)]) -> PathBuf
+    {
+        let folder = std::env::temp_dir().join(format!("bevyx_ron_{}_{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&folder);
+        for (path, text) in files
+        {
+            let path = folder.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, text).unwrap();
+        }
+        folder
+    }
+
+    fn test_app(folder: &Path) -> App
+    {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin{ file_path: folder.display().to_string(), ..default() },
+            RonAssetPlugin::<Level>::default(),
+        ));
+        app
+    }
+
+    // updates the app until the asset and everything it depends on is done loading, one way or the other
+    fn load(app: &mut App, path: &str) -> (Handle<Level>, RecursiveDependencyLoadState)
+    {
+        let handle = app.world().resource::<AssetServer>().load::<Level>(path.to_owned());
+        wait(app, handle, path)
+    }
+
+    fn load_strict(app: &mut App, path: &str, strict: bool) -> (Handle<Level>, RecursiveDependencyLoadState)
+    {
+        let handle = app.world().resource::<AssetServer>()
+            .load_with_settings::<Level, RonLoaderSettings>(path.to_owned(), move |settings| settings.strict = strict);
+        wait(app, handle, path)
+    }
+
+    fn wait(app: &mut App, handle: Handle<Level>, path: &str) -> (Handle<Level>, RecursiveDependencyLoadState)
+    {
+        for _ in 0..500
+        {
+            app.update();
+            let state = app.world().resource::<AssetServer>().recursive_dependency_load_state(handle.id());
+            if matches!(state, RecursiveDependencyLoadState::Loaded | RecursiveDependencyLoadState::Failed(_))
+            {
+                return (handle, state);
+            }
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        panic!("[{path}] did not finish loading");
+    }
+
+    #[test]
+    fn test_load_includes()
+    {
+        let folder = test_folder("includes", &[
+            ("shared/base.ron", "(name: \"base\", size: 3)"),
+            ("levels/sized.ron", "(include: \"/shared/base.ron\", size: 5)"),
+            ("levels/one.ron", "(include: [\"sized.ron\"], name: \"one\")"),
+        ]);
+        let mut app = test_app(&folder);
+
+        let (handle, state) = load(&mut app, "levels/one.ron");
+        assert!(matches!(state, RecursiveDependencyLoadState::Loaded));
+        let level = app.world().resource::<Assets<Level>>().get(&handle).unwrap();
+        assert_eq!((level.name.as_str(), level.size), ("one", 5));
+        std::fs::remove_dir_all(&folder).unwrap();
+    }
+
+    #[test]
+    fn test_include_cycle()
+    {
+        let folder = test_folder("include_cycle", &[
+            ("levels/one.ron", "(include: \"two.ron\", name: \"one\")"),
+            ("levels/two.ron", "(include: \"one.ron\", size: 2)"),
+        ]);
+        let mut app = test_app(&folder);
+
+        let (_, state) = load(&mut app, "levels/one.ron");
+        let RecursiveDependencyLoadState::Failed(error) = state else
+        {
+            panic!("expected the cycle to fail the load");
+        };
+        assert!(error.to_string().contains("[levels/one.ron] includes itself"), "{error}");
+        std::fs::remove_dir_all(&folder).unwrap();
+    }
+
+    #[test]
+    fn test_include_type_error()
+    {
+        let folder = test_folder("include_type_error", &[
+            ("shared/base.ron", "(size: \"three\")"),
+            ("levels/one.ron", "(include: \"/shared/base.ron\", name: \"one\")"),
+        ]);
+        let mut app = test_app(&folder);
+
+        // the merged value has no position to report, the path still tells which asset is broken
+        let (_, state) = load(&mut app, "levels/one.ron");
+        let RecursiveDependencyLoadState::Failed(error) = state else
+        {
+            panic!("expected the included type error to fail the load");
+        };
+        assert!(error.to_string().contains("[levels/one.ron] with its includes and migrations applied"), "{error}");
+        std::fs::remove_dir_all(&folder).unwrap();
+    }
+
+    #[test]
+    fn test_asset_ref_dependencies()
+    {
+        let folder = test_folder("asset_ref", &[
+            ("levels/one.ron", "(name: \"one\", next: Some(\"levels/two.ron\"))"),
+            ("levels/two.ron", "(name: \"two\")"),
+            ("levels/broken.ron", "(name: \"broken\", next: Some(\"levels/missing.ron\"))"),
+        ]);
+        let mut app = test_app(&folder);
+
+        // the asset only counts as loaded once the level it names has loaded too
+        let (handle, state) = load(&mut app, "levels/one.ron");
+        assert!(matches!(state, RecursiveDependencyLoadState::Loaded));
+        let next = app.world().resource::<Assets<Level>>().get(&handle).unwrap().next.clone().unwrap();
+        assert!(matches!(app.world().resource::<AssetServer>().load_state(next.handle.id()), LoadState::Loaded));
+        assert_eq!(app.world().resource::<Assets<Level>>().get(&next.handle).unwrap().name, "two");
+
+        let (_, state) = load(&mut app, "levels/broken.ron");
+        assert!(matches!(state, RecursiveDependencyLoadState::Failed(_)));
+        std::fs::remove_dir_all(&folder).unwrap();
+    }
+
+    #[test]
+    fn test_strict_unknown_fields()
+    {
+        let folder = test_folder("strict", &[
+            ("levels/typo.ron", "(name: \"typo\", szie: 3)"),
+            ("levels/lax.ron", "(name: \"lax\", szie: 3)"),
+        ]);
+        let mut app = test_app(&folder);
+
+        let (_, state) = load_strict(&mut app, "levels/typo.ron", true);
+        let RecursiveDependencyLoadState::Failed(error) = state else
+        {
+            panic!("expected strict mode to reject the unknown field");
+        };
+        assert!(error.to_string().contains("Unknown fields: szie"), "{error}");
+
+        // the warning lists the same fields strict mode rejects
+        let (handle, state) = load_strict(&mut app, "levels/lax.ron", false);
+        assert!(matches!(state, RecursiveDependencyLoadState::Loaded));
+        app.update();
+        assert_eq!(app.world().resource::<RonLoadErrors>().warnings.get("levels/lax.ron").unwrap(), "Ignores unknown fields: szie");
+        let level = app.world().resource::<Assets<Level>>().get(&handle).unwrap();
+        assert_eq!((level.name.as_str(), level.size), ("lax", 0));
+        let value = ron::from_str::<Value>("(name: \"lax\", szie: 3)").unwrap();
+        assert_eq!(unknown_fields_of(&value, level).unwrap(), vec!["szie".to_owned()]);
+        std::fs::remove_dir_all(&folder).unwrap();
+    }
+
+    #[test]
+    fn test_save_load_round_trip()
+    {
+        let folder = test_folder("round_trip", &[("levels/two.ron", "(name: \"two\")")]);
+        let mut app = test_app(&folder);
+
+        let level = Level{ name: "one".into(), size: 4, next: Some(AssetRef::new("levels/two.ron")) };
+        let asset_server = app.world().resource::<AssetServer>().clone();
+        bevy::tasks::block_on(save_ron_asset(&asset_server, "levels/one.ron", level)).unwrap();
+
+        let (handle, state) = load_strict(&mut app, "levels/one.ron", true);
+        assert!(matches!(state, RecursiveDependencyLoadState::Loaded));
+        let loaded = app.world().resource::<Assets<Level>>().get(&handle).unwrap();
+        assert_eq!((loaded.name.as_str(), loaded.size), ("one", 4));
+        assert_eq!(loaded.next.as_ref().unwrap().path, PathBuf::from("levels/two.ron"));
+        std::fs::remove_dir_all(&folder).unwrap();
     }
 }
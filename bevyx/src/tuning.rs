@@ -0,0 +1,182 @@
+use crate::asset_ref::LoadDependencies;
+use crate::loading::LoadingAssets;
+use crate::ron::RonAssetPlugin;
+
+use base::debug_name;
+use base::tuning::Error;
+use base::tuning::Sources;
+use base::tuning::Tuning;
+use bevy::prelude::*;
+use std::marker::PhantomData;
+
+// sent after the resource of a tuning type has been swapped for a reloaded one
+#[derive(Event)]
+pub struct TuningChanged<T>
+{
+    _marker: PhantomData<T>,
+}
+
+impl<T> Default for TuningChanged<T>
+{
+    fn default() -> Self
+    {
+        Self
+        {
+            _marker: PhantomData,
+        }
+    }
+}
+
+// the PreStartup systems inserting the tuning resources, anything touching the files on disk first runs before it
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TuningLoadSet;
+
+#[derive(Resource)]
+struct TuningHandle<T: Asset>(Handle<T>);
+
+// the layer every field of the tuning resource was resolved from, whether the asset file needed an update
+// when the game started, and why the last load failed if it did
+#[derive(Resource)]
+pub struct TuningStatus<T>
+{
+    pub sources: Sources,
+    pub outdated: bool,
+    pub error: Option<Error>,
+    _marker: PhantomData<T>,
+}
+
+impl<T> Default for TuningStatus<T>
+{
+    fn default() -> Self
+    {
+        Self
+        {
+            sources: Sources::new(),
+            outdated: false,
+            error: None,
+            _marker: PhantomData,
+        }
+    }
+}
+
+// loads a tuning type as a resource, then keeps it in sync with its RON asset while the game runs
+pub struct TuningPlugin<T>
+{
+    _marker: PhantomData<T>,
+}
+
+impl<T> Default for TuningPlugin<T>
+{
+    fn default() -> Self
+    {
+        Self
+        {
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Plugin for TuningPlugin<T>
+where
+    for<'de> T: Tuning + Asset + Resource + Default + LoadDependencies + serde::Serialize + serde::Deserialize<'de>,
+{
+    fn build(&self, app: &mut App)
+    {
+        app
+            .add_plugins(RonAssetPlugin::<T>::default())
+            .init_resource::<LoadingAssets>()
+            .add_event::<TuningChanged<T>>()
+            .add_systems(PreStartup, load_tuning::<T>.in_set(TuningLoadSet))
+            .add_systems(PreUpdate, swap_tuning::<T>);
+    }
+}
+
+fn load_tuning<T>
+(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut loading: ResMut<LoadingAssets>,
+)
+where
+    for<'de> T: Tuning + Asset + Resource + Default + LoadDependencies + serde::Serialize + serde::Deserialize<'de>,
+{
+    // read synchronously so Startup systems can rely on the resource, the asset only drives reloads
+    let mut status = TuningStatus::<T>::default();
+    let mut tuning = match T::load_layered()
+    {
+        Ok(layered) =>
+        {
+            status.sources = layered.sources;
+            status.outdated = layered.outdated;
+            layered.tuning
+        },
+        Err(err) =>
+        {
+            // the game still starts, the problem stays on show until a reload fixes it
+            eprintln!("{} -- {}", debug_name!(), err);
+            status.error = Some(err);
+            T::default()
+        }
+    };
+    tuning.load_dependencies(&mut asset_server.clone());
+    commands.insert_resource(tuning);
+    commands.insert_resource(status);
+
+    // the loading state waits for the asset, and through it for every asset its AssetRef fields name
+    let handle = asset_server.load(T::path());
+    loading.add(handle.clone());
+    commands.insert_resource(TuningHandle::<T>(handle));
+}
+
+fn swap_tuning<T>
+(
+    mut asset_events: EventReader<AssetEvent<T>>,
+    mut changed_events: EventWriter<TuningChanged<T>>,
+    asset_server: Res<AssetServer>,
+    assets: Res<Assets<T>>,
+    handle: Option<Res<TuningHandle<T>>>,
+    mut tuning: ResMut<T>,
+    mut status: ResMut<TuningStatus<T>>,
+)
+where
+    for<'de> T: Tuning + Asset + Resource + Default + LoadDependencies + serde::Serialize + serde::Deserialize<'de>,
+{
+    let Some(handle) = handle else
+    {
+        return;
+    };
+
+    for event in asset_events.read()
+    {
+        let AssetEvent::Modified { id } = event else
+        {
+            continue;
+        };
+        if *id != handle.0.id()
+        {
+            continue;
+        }
+        let Some(asset) = assets.get(*id) else
+        {
+            continue;
+        };
+
+        // layer the user file and overrides over the reloaded asset again, keeping the last good values on errors
+        match T::layer_over(asset)
+        {
+            Ok(mut layered) =>
+            {
+                layered.tuning.load_dependencies(&mut asset_server.clone());
+                *tuning = layered.tuning;
+                status.sources = layered.sources;
+                status.error = None;
+                changed_events.send(TuningChanged::default());
+            },
+            Err(err) =>
+            {
+                eprintln!("{} -- {}", debug_name!(), err);
+                status.error = Some(err);
+            }
+        }
+    }
+}
@@ -0,0 +1,204 @@
+
+use crate::grid::CellState;
+use crate::grid::Grid;
+
+use base::extents::Point;
+
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum DefuseOutcome
+{
+    // there was a mine, it has been removed
+    Defused,
+    // the cell was safe, nothing to clean up
+    Wasted,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum Error
+{
+    OutOfBounds(Point),
+    AlreadyRevealed(Point),
+    NonPlayable(Point),
+    // the penalty of a wasted defuse must be covered up front
+    OutOfResources{ amount: i32, penalty: i32 },
+    GameOver,
+}
+
+impl Display for Error
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result
+    {
+        match self
+        {
+            Error::OutOfBounds(pos) => write!(f, "Defuse -- ({}, {}) out of bounds", pos.x, pos.y),
+            Error::AlreadyRevealed(pos) => write!(f, "Defuse -- ({}, {}) is already revealed", pos.x, pos.y),
+            Error::NonPlayable(pos) => write!(f, "Defuse -- ({}, {}) is not playable", pos.x, pos.y),
+            Error::OutOfResources{ amount, penalty } => write!(f, "Defuse -- {amount} resources can't cover the penalty of {penalty}"),
+            Error::GameOver => write!(f, "Defuse -- the game is over"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DefuseRules
+{
+    pub starting_amount: i32,
+    pub reward: i32,
+    pub penalty: i32,
+}
+
+impl Default for DefuseRules
+{
+    fn default() -> Self
+    {
+        Self
+        {
+            starting_amount: 10,
+            reward: 10,
+            penalty: 5,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub struct Resources
+{
+    pub amount: i32,
+}
+
+impl Resources
+{
+    pub fn new(rules: &DefuseRules) -> Self
+    {
+        Self
+        {
+            amount: rules.starting_amount,
+        }
+    }
+
+    pub fn can_defuse(&self, rules: &DefuseRules) -> bool
+    {
+        self.amount >= rules.penalty
+    }
+
+    pub fn apply(&mut self, rules: &DefuseRules, outcome: DefuseOutcome)
+    {
+        match outcome
+        {
+            DefuseOutcome::Defused => self.amount += rules.reward,
+            DefuseOutcome::Wasted => self.amount -= rules.penalty,
+        }
+    }
+}
+
+// defuse a cell the player believes to be mined, paying out the reward or the penalty from resources.
+// either way the cell ends up revealed, and the adjacency of its neighbours is kept in sync.
+pub fn defuse(grid: &mut Grid, pos: Point, rules: &DefuseRules, resources: &mut Resources) -> Result<DefuseOutcome, Error>
+{
+    let state = *grid.states.get_by_index2(pos).ok_or(Error::OutOfBounds(pos))?;
+    if state.contains(CellState::NonPlayable)
+    {
+        return Err(Error::NonPlayable(pos));
+    }
+    if state.contains(CellState::Revealed)
+    {
+        return Err(Error::AlreadyRevealed(pos));
+    }
+    if !resources.can_defuse(rules)
+    {
+        return Err(Error::OutOfResources{ amount: resources.amount, penalty: rules.penalty });
+    }
+
+    let outcome = if grid.remove_mine(pos)
+    {
+        DefuseOutcome::Defused
+    }
+    else
+    {
+        DefuseOutcome::Wasted
+    };
+    grid.states[pos].insert(CellState::Revealed);
+    resources.apply(rules, outcome);
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::session::Session;
+
+    // a mine in the middle of a 3x3 board, with one more in the corner so the game isn't won by defusing it
+    fn board() -> Grid
+    {
+        let mut grid = Grid::new(3, 3);
+        grid.place_mine(Point::new(1, 1));
+        grid.place_mine(Point::new(0, 0));
+        grid
+    }
+
+    #[test]
+    fn test_defuse_mine()
+    {
+        let mut grid = board();
+        let rules = DefuseRules::default();
+        let mut resources = Resources::new(&rules);
+
+        assert_eq!(defuse(&mut grid, Point::new(1, 1), &rules, &mut resources), Ok(DefuseOutcome::Defused));
+        assert_eq!(resources.amount, rules.starting_amount + rules.reward);
+        assert_eq!(grid.states[Point::new(1, 1)], CellState::Revealed);
+        assert_eq!(grid.adjacency[Point::new(2, 2)], 0);
+        assert_eq!(grid.adjacency[Point::new(1, 0)], 1);
+        assert!(grid.adjacency_in_sync());
+
+        assert_eq!(defuse(&mut grid, Point::new(1, 1), &rules, &mut resources), Err(Error::AlreadyRevealed(Point::new(1, 1))));
+        assert_eq!(defuse(&mut grid, Point::new(3, 1), &rules, &mut resources), Err(Error::OutOfBounds(Point::new(3, 1))));
+    }
+
+    #[test]
+    fn test_defuse_wasted()
+    {
+        let mut grid = board();
+        let rules = DefuseRules::default();
+        let mut resources = Resources::new(&rules);
+
+        assert_eq!(defuse(&mut grid, Point::new(2, 2), &rules, &mut resources), Ok(DefuseOutcome::Wasted));
+        assert_eq!(resources.amount, rules.starting_amount - rules.penalty);
+        assert_eq!(grid.states[Point::new(2, 2)], CellState::Revealed);
+        assert_eq!(grid.mine_count(), 2);
+        assert!(grid.adjacency_in_sync());
+    }
+
+    #[test]
+    fn test_defuse_out_of_resources()
+    {
+        let mut grid = board();
+        let rules = DefuseRules::default();
+        let mut resources = Resources{ amount: rules.penalty - 1 };
+
+        let err = defuse(&mut grid, Point::new(1, 1), &rules, &mut resources);
+        assert_eq!(err, Err(Error::OutOfResources{ amount: rules.penalty - 1, penalty: rules.penalty }));
+        assert_eq!(resources.amount, rules.penalty - 1);
+        assert_eq!(grid.states[Point::new(1, 1)], CellState::Mine);
+    }
+
+    #[test]
+    fn test_defuse_game_over()
+    {
+        let mut grid = board();
+        let rules = DefuseRules::default();
+        let mut resources = Resources::new(&rules);
+        let mut session = Session::new(&grid);
+
+        session.reveal(&mut grid, Point::new(0, 0));
+        assert!(session.is_over());
+        assert_eq!(session.defuse(&mut grid, Point::new(1, 1), &rules, &mut resources), Err(Error::GameOver));
+        assert_eq!(resources.amount, rules.starting_amount);
+        assert_eq!(grid.mine_count(), 2);
+    }
+}
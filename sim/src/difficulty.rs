@@ -0,0 +1,253 @@
+
+use crate::grid::CellState;
+use crate::grid::Grid;
+use crate::solver::Knowledge;
+use crate::solver::Solver;
+use crate::solver::Technique;
+
+use base::array2::Array2;
+use base::extents::Neighbours;
+use base::extents::Point;
+use base::flood;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, PartialOrd, Ord, Hash)]
+pub enum Rating
+{
+    Beginner,
+    Intermediate,
+    Expert,
+    Evil,
+}
+
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Difficulty
+{
+    pub trivial_steps: u32,
+    pub subset_steps: u32,
+    pub global_count_steps: u32,
+    pub forced_guesses: u32,
+    pub three_bv: u32,
+    pub openings: u32,
+}
+
+impl Difficulty
+{
+    // plays the board with the solver, guessing a safe cell whenever logic runs out.
+    // None when the board can't be played out, like when a revealed number disagrees with the mines.
+    pub fn rate(grid: &Grid) -> Option<Self>
+    {
+        let mut difficulty = Self
+        {
+            three_bv: three_bv(grid),
+            openings: openings(grid),
+            ..Default::default()
+        };
+
+        let mut solver = Solver::from_grid(grid);
+        if !solver.knowledge().raw_iter().any(|k| matches!(k, Knowledge::Safe(_)))
+        {
+            // the first click is free
+            if let Some(pos) = first_click(grid)
+            {
+                solver.reveal(grid, pos);
+            }
+        }
+
+        while !solver.is_solved()
+        {
+            if let Some(step) = solver.next_step()
+            {
+                match step.technique
+                {
+                    Technique::Trivial => difficulty.trivial_steps += 1,
+                    Technique::Subset => difficulty.subset_steps += 1,
+                    Technique::GlobalCount => difficulty.global_count_steps += 1,
+                }
+                solver.apply(grid, &step);
+                continue;
+            }
+
+            // on a consistent board the global count finds the mines once only mines are left, so a safe cell exists
+            let (guess, _) = solver.knowledge().enumerate()
+                .find(|(pos, k)| **k == Knowledge::Unknown && !grid.states[*pos].contains(CellState::Mine))?;
+            difficulty.forced_guesses += 1;
+            solver.reveal(grid, guess);
+        }
+
+        Some(difficulty)
+    }
+
+    // weighted effort per 3BV, so larger boards of the same style score alike
+    pub fn score(&self) -> f32
+    {
+        let effort = self.trivial_steps
+            + self.subset_steps * 4
+            + self.global_count_steps * 8
+            + self.forced_guesses * 25;
+        effort as f32 / self.three_bv.max(1) as f32
+    }
+
+    pub fn rating(&self) -> Rating
+    {
+        let score = self.score();
+        if self.forced_guesses > 2 || score >= 4.0
+        {
+            Rating::Evil
+        }
+        else if self.forced_guesses > 0 || score >= 2.0
+        {
+            Rating::Expert
+        }
+        else if self.subset_steps > 0 || self.global_count_steps > 0
+        {
+            Rating::Intermediate
+        }
+        else
+        {
+            Rating::Beginner
+        }
+    }
+}
+
+fn is_safe(grid: &Grid, pos: Point) -> bool
+{
+    !grid.states[pos].intersects(CellState::Mine | CellState::NonPlayable)
+}
+
+fn is_opening_cell(grid: &Grid, pos: Point) -> bool
+{
+    is_safe(grid, pos) && grid.adjacency[pos] == 0
+}
+
+fn first_click(grid: &Grid) -> Option<Point>
+{
+    let size = grid.size();
+    size.index2_space().find(|pos| is_opening_cell(grid, *pos))
+        .or_else(|| size.index2_space().find(|pos| is_safe(grid, *pos)))
+}
+
+// connected regions of safe cells without adjacent mines
+fn opening_labels(grid: &Grid) -> (Array2<u32>, u32)
+{
+    flood::connected_components(&grid.states, Neighbours::All, |pos, _| is_opening_cell(grid, pos))
+}
+
+pub fn openings(grid: &Grid) -> u32
+{
+    opening_labels(grid).1
+}
+
+// minimum number of clicks to clear the board: one per opening, plus one per numbered cell no opening reveals
+pub fn three_bv(grid: &Grid) -> u32
+{
+    let size = grid.size();
+    let (labels, openings) = opening_labels(grid);
+    let isolated = size.index2_space()
+        .filter(|pos|
+        {
+            is_safe(grid, *pos)
+                && labels[*pos] == 0
+                && size.neighbours::<{ Neighbours::All.bits() }>(*pos).all(|n| labels[n] == 0)
+        })
+        .count() as u32;
+    openings + isolated
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn test_three_bv()
+    {
+        let mut grid = Grid::new(3, 3);
+        grid.place_mine(Point::new(1, 1));
+        assert_eq!(openings(&grid), 0);
+        assert_eq!(three_bv(&grid), 8);
+
+        let mut grid = Grid::new(3, 1);
+        grid.place_mine(Point::new(0, 0));
+        assert_eq!(openings(&grid), 1);
+        assert_eq!(three_bv(&grid), 1);
+    }
+
+    #[test]
+    fn test_rate()
+    {
+        let mut grid = Grid::new(3, 1);
+        grid.place_mine(Point::new(0, 0));
+        let difficulty = Difficulty::rate(&grid).unwrap();
+        assert_eq!(difficulty.trivial_steps, 1);
+        assert_eq!(difficulty.forced_guesses, 0);
+        assert_eq!(difficulty.rating(), Rating::Beginner);
+
+        let mut grid = Grid::new(2, 1);
+        grid.place_mine(Point::new(0, 0));
+        let difficulty = Difficulty::rate(&grid).unwrap();
+        assert_eq!(difficulty.forced_guesses, 0);
+        assert_eq!(difficulty.three_bv, 1);
+
+        let mut grid = Grid::new(2, 2);
+        grid.place_mine(Point::new(0, 0));
+        grid.place_mine(Point::new(1, 1));
+        let difficulty = Difficulty::rate(&grid).unwrap();
+        assert_eq!(difficulty.global_count_steps + difficulty.trivial_steps + difficulty.subset_steps, 1);
+    }
+
+    #[test]
+    fn test_subset_only()
+    {
+        // 1 1 1 over three unknown cells: no number decides on its own, but the cells of the left 1 all
+        // touch the middle 1, so they hold its only mine and the bottom right cell is safe
+        let mut grid = Grid::new(3, 2);
+        grid.place_mine(Point::new(1, 1));
+        for x in 0..3
+        {
+            grid.reveal(Point::new(x, 0));
+        }
+
+        let step = Solver::from_grid(&grid).next_step().unwrap();
+        assert_eq!(step.technique, Technique::Subset);
+        assert_eq!(step.safe, vec![Point::new(2, 1)]);
+        assert!(step.mines.is_empty());
+
+        let difficulty = Difficulty::rate(&grid).unwrap();
+        assert_eq!(difficulty.subset_steps, 1);
+        assert_eq!(difficulty.global_count_steps, 0);
+        assert_eq!(difficulty.forced_guesses, 0);
+    }
+
+    #[test]
+    fn test_global_count_only()
+    {
+        // the non playable cell walls the left cell off from every number, only the mine count reaches it
+        let mut grid = Grid::new(3, 1);
+        grid.states[Point::new(1, 0)] |= CellState::NonPlayable;
+        grid.place_mine(Point::new(0, 0));
+        grid.reveal(Point::new(2, 0));
+
+        let step = Solver::from_grid(&grid).next_step().unwrap();
+        assert_eq!(step.technique, Technique::GlobalCount);
+        assert_eq!(step.mines, vec![Point::new(0, 0)]);
+        assert!(step.safe.is_empty());
+
+        let difficulty = Difficulty::rate(&grid).unwrap();
+        assert_eq!(difficulty.global_count_steps, 1);
+        assert_eq!(difficulty.trivial_steps + difficulty.subset_steps, 0);
+        assert_eq!(difficulty.forced_guesses, 0);
+    }
+
+    #[test]
+    fn test_rate_inconsistent()
+    {
+        // the revealed number claims a mine next to it that isn't there, so the solver marks the wrong cell
+        // and the count of mines left can't tell which of the last two cells is the mine
+        let mut grid = Grid::new(4, 1);
+        grid.place_mine(Point::new(2, 0));
+        grid.place_mine(Point::new(3, 0));
+        grid.adjacency[Point::new(0, 0)] = 1;
+        grid.states[Point::new(0, 0)] |= CellState::Revealed;
+        assert_eq!(Difficulty::rate(&grid), None);
+    }
+}
@@ -1,6 +1,9 @@
 
 use base::array2;
+use base::bitarray2::BitArray2;
 use base::extents;
+use base::extents::Point;
+use base::flood;
 
 use bitflags::bitflags;
 
@@ -10,13 +13,23 @@ bitflags!
     #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
     pub struct CellState: u8
     {
-        const None = 0 << 0;
+        const None = 0;
         const Mine = 1 << 0;
         const Revealed = 1 << 1;
         const NonPlayable = 1 << 2;
     }
 }
 
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Reveal
+{
+    // out of bounds, not playable or already revealed
+    Nothing,
+    // number of cells revealed, including the cascade through cells without adjacent mines
+    Safe(u32),
+    Mine,
+}
+
 #[derive(Debug, Clone)]
 pub struct Grid
 {
@@ -49,29 +62,323 @@ impl Grid
         self.states.size()
     }
 
+    pub fn resize(&mut self, new_size: extents::Extents, anchor: array2::Anchor)
+    {
+        self.states.resize(new_size, CellState::None, anchor);
+        self.adjacency.resize(new_size, 0, anchor);
+        self.update_adjacency();
+    }
+
+    pub fn crop(&mut self, rect: extents::Rect) -> Result<(), array2::Error>
+    {
+        self.states.crop(rect)?;
+        self.adjacency.crop(rect)?;
+        self.update_adjacency();
+        Ok(())
+    }
+
+    // copies the cells of src with its origin at dst_pos, clipped to this grid
+    pub fn blit(&mut self, src: &Grid, dst_pos: Point) -> Option<extents::Rect>
+    {
+        let written = self.states.blit(&src.states, dst_pos);
+        self.update_adjacency();
+        written
+    }
+
+    // the layout of mines and non playable cells, identical for every rotation and mirror of the same board
+    pub fn canonical_layout(&self) -> array2::Array2<CellState>
+    {
+        let mut layout = self.states.clone();
+        for state in layout.iter_mut()
+        {
+            *state &= CellState::Mine | CellState::NonPlayable;
+        }
+
+        array2::Symmetry::ALL.iter()
+            .map(|symmetry| layout.transformed(*symmetry))
+            .min_by(|a, b|
+            {
+                (a.width(), a.height()).cmp(&(b.width(), b.height()))
+                    .then_with(|| a.raw_iter().cmp(b.raw_iter()))
+            })
+            .expect("Grid -- there is always a symmetry")
+    }
+
     pub fn clear(&mut self)
     {
         self.states.fill_with(CellState::None);
         self.adjacency.fill_with(0);
     }
 
+    // one bit per cell with any of the flags set
+    pub fn mask(&self, flags: CellState) -> BitArray2
+    {
+        BitArray2::from_array2(&self.states, |state| state.intersects(flags))
+    }
+
+    // cells in line of sight of origin, non playable cells such as islands block vision
+    pub fn visible_from(&self, origin: Point, radius: Option<i32>) -> BitArray2
+    {
+        base::sight::field_of_view(self.size(), origin, radius, |pos|
+        {
+            self.states[pos].contains(CellState::NonPlayable)
+        })
+    }
+
+    pub fn mine_count(&self) -> u32
+    {
+        self.states.raw_iter().filter(|state| state.contains(CellState::Mine)).count() as u32
+    }
+
+    pub fn playable_count(&self) -> u32
+    {
+        self.states.raw_iter().filter(|state| !state.contains(CellState::NonPlayable)).count() as u32
+    }
+
+    // every playable cell without a mine has been revealed
+    pub fn is_cleared(&self) -> bool
+    {
+        self.states.raw_iter().all(|state|
+        {
+            state.intersects(CellState::Mine | CellState::NonPlayable | CellState::Revealed)
+        })
+    }
+
+    pub fn reveal(&mut self, pos: Point) -> Reveal
+    {
+        let Some(state) = self.states.get_by_index2(pos) else
+        {
+            return Reveal::Nothing;
+        };
+        if state.intersects(CellState::NonPlayable | CellState::Revealed)
+        {
+            return Reveal::Nothing;
+        }
+        if state.contains(CellState::Mine)
+        {
+            self.states[pos].insert(CellState::Revealed);
+            return Reveal::Mine;
+        }
+
+        // the cells without adjacent mines connected to pos open up, along with their neighbours
+        let closed = CellState::NonPlayable | CellState::Revealed | CellState::Mine;
+        let openings = flood::flood_fill
+        (
+            &self.states,
+            pos,
+            extents::Neighbours::All,
+            |pos, state| !state.intersects(closed) && self.adjacency[pos] == 0
+        );
+
+        let size = self.size();
+        let mut count = 0;
+        let cells = std::iter::once(pos)
+            .chain(openings.ones().flat_map(|pos| size.neighbours::<{ extents::Neighbours::All.bits() }>(pos)));
+        for pos in cells
+        {
+            let state = &mut self.states[pos];
+            if !state.intersects(closed)
+            {
+                state.insert(CellState::Revealed);
+                count += 1;
+            }
+        }
+        Reveal::Safe(count)
+    }
+
+    fn count_adjacent_mines(&self, pos: Point) -> u8
+    {
+        let mut adj = 0;
+        for neighbour_pos in self.size().neighbours::<{ base::extents::Neighbours::All.bits() }>(pos)
+        {
+            let state = self.states.get_by_index2(neighbour_pos).unwrap();
+            if state.intersects(CellState::Mine)
+            {
+                adj += 1;
+            }
+        }
+        assert!(adj <= 8);
+        adj
+    }
+
     pub fn update_adjacency(&mut self)
     {
         let size = self.states.size();
         for pos in size.index2_space()
         {
-            let mut adj = 0;
-            for neighbour_pos in size.neighbours::<{ base::extents::Neighbours::All.bits() }>(pos)
+            let adj = self.count_adjacent_mines(pos);
+            self.adjacency.set_by_index2(pos, adj).unwrap();
+        }
+    }
+
+    // places a mine at pos, only touching the adjacency of its neighbours
+    pub fn place_mine(&mut self, pos: Point) -> bool
+    {
+        let Some(state) = self.states.get_by_index2_mut(pos) else
+        {
+            return false;
+        };
+        if state.contains(CellState::Mine)
+        {
+            return false;
+        }
+        state.insert(CellState::Mine);
+
+        for neighbour_pos in self.size().neighbours::<{ base::extents::Neighbours::All.bits() }>(pos)
+        {
+            let adj = &mut self.adjacency[neighbour_pos];
+            assert!(*adj < 8);
+            *adj += 1;
+        }
+        // debug_assert! still compiles its condition in release builds, where the check doesn't exist
+        #[cfg(debug_assertions)]
+        debug_assert!(self.adjacency_in_sync(), "Grid -- adjacency out of sync after placing a mine at ({}, {})", pos.x, pos.y);
+        true
+    }
+
+    // removes the mine at pos, only touching the adjacency of its neighbours
+    pub fn remove_mine(&mut self, pos: Point) -> bool
+    {
+        let Some(state) = self.states.get_by_index2_mut(pos) else
+        {
+            return false;
+        };
+        if !state.contains(CellState::Mine)
+        {
+            return false;
+        }
+        state.remove(CellState::Mine);
+
+        for neighbour_pos in self.size().neighbours::<{ base::extents::Neighbours::All.bits() }>(pos)
+        {
+            let adj = &mut self.adjacency[neighbour_pos];
+            assert!(*adj > 0);
+            *adj -= 1;
+        }
+        #[cfg(debug_assertions)]
+        debug_assert!(self.adjacency_in_sync(), "Grid -- adjacency out of sync after removing the mine at ({}, {})", pos.x, pos.y);
+        true
+    }
+
+    // compares the incrementally maintained adjacency against a full recompute, O(N) so debug builds only
+    #[cfg(debug_assertions)]
+    pub(crate) fn adjacency_in_sync(&self) -> bool
+    {
+        self.size().index2_space().all(|pos| self.adjacency[pos] == self.count_adjacent_mines(pos))
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    // xorshift, good enough to drive mutation sequences without pulling in a rng crate
+    struct TestRng(u64);
+
+    impl TestRng
+    {
+        fn next(&mut self) -> u64
+        {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn pos(&mut self, size: extents::Extents) -> Point
+        {
+            let index = (self.next() % size.num_elements() as u64) as usize;
+            size.get_index2(index).unwrap()
+        }
+    }
+
+    #[test]
+    fn test_place_remove_mine()
+    {
+        let mut grid = Grid::new(3, 3);
+        assert!(grid.place_mine(Point::new(1, 1)));
+        assert!(!grid.place_mine(Point::new(1, 1)));
+        assert!(!grid.place_mine(Point::new(3, 3)));
+        assert_eq!(grid.adjacency[Point::new(0, 0)], 1);
+        assert_eq!(grid.adjacency[Point::new(1, 1)], 0);
+
+        assert!(grid.remove_mine(Point::new(1, 1)));
+        assert!(!grid.remove_mine(Point::new(1, 1)));
+        assert!(grid.adjacency.raw_iter().all(|adj| *adj == 0));
+    }
+
+    #[test]
+    fn test_resize_keeps_adjacency()
+    {
+        let mut grid = Grid::new(3, 3);
+        grid.place_mine(Point::new(0, 0));
+        grid.resize(extents::Extents::new(2, 2), array2::Anchor::BottomRight);
+        assert!(grid.adjacency.raw_iter().all(|adj| *adj == 0));
+
+        grid.resize(extents::Extents::new(3, 3), array2::Anchor::BottomRight);
+        grid.place_mine(Point::new(2, 2));
+        let mut other = Grid::new(2, 2);
+        other.blit(&grid, Point::new(-1, -1));
+        assert_eq!(other.adjacency[Point::new(0, 0)], 1);
+
+        grid.crop(extents::Rect::new(Point::new(1, 1), extents::Extents::new(2, 2))).unwrap();
+        assert_eq!(grid.adjacency, other.adjacency);
+    }
+
+    #[test]
+    fn test_canonical_layout()
+    {
+        let mut grid = Grid::new(3, 2);
+        grid.place_mine(Point::new(0, 0));
+        let mut mirrored = Grid::new(2, 3);
+        mirrored.place_mine(Point::new(1, 2));
+        mirrored.reveal(Point::new(0, 0));
+        assert_eq!(grid.canonical_layout(), mirrored.canonical_layout());
+
+        let mut other = Grid::new(3, 2);
+        other.place_mine(Point::new(1, 0));
+        assert_ne!(grid.canonical_layout(), other.canonical_layout());
+    }
+
+    #[test]
+    fn test_reveal_cascade()
+    {
+        let mut grid = Grid::new(4, 4);
+        grid.place_mine(Point::new(0, 0));
+        assert_eq!(grid.reveal(Point::new(3, 3)), Reveal::Safe(15));
+        assert_eq!(grid.reveal(Point::new(3, 3)), Reveal::Nothing);
+        assert!(grid.is_cleared());
+        assert_eq!(grid.reveal(Point::new(0, 0)), Reveal::Mine);
+    }
+
+    #[test]
+    fn test_random_mutations_match_full_update()
+    {
+        for seed in 1..=16_u64
+        {
+            let mut rng = TestRng(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+            let size = extents::Extents::new(1 + (rng.next() % 12) as i32, 1 + (rng.next() % 12) as i32);
+            let mut grid = Grid::from_size(size);
+
+            for _ in 0..256
             {
-                let state = self.states.get_by_index2(neighbour_pos).unwrap();
-                if state.intersects(CellState::Mine)
+                let pos = rng.pos(size);
+                if rng.next() & 1 == 0
+                {
+                    grid.place_mine(pos);
+                }
+                else
                 {
-                    adj += 1;
+                    grid.remove_mine(pos);
                 }
+                assert!(grid.adjacency_in_sync());
             }
-            assert!(adj <= 8);
-            self.adjacency.set_by_index2(pos, adj).unwrap();
+
+            let mut full = grid.clone();
+            full.update_adjacency();
+            assert_eq!(grid.adjacency, full.adjacency);
+            assert_eq!(grid.adjacency, grid.mask(CellState::Mine).neighbour_counts(extents::Neighbours::All));
         }
     }
 }
-
@@ -0,0 +1,72 @@
+
+use crate::grid::Grid;
+use crate::solver::Solver;
+
+use base::extents::Point;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HintKind
+{
+    Safe,
+    Mine,
+    // nothing is certain, the cell least likely to hold a mine
+    Guess(f32),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hint
+{
+    pub pos: Point,
+    pub kind: HintKind,
+    pub explanation: String,
+}
+
+// the next logically certain cell, safe cells first, otherwise the best guess
+pub fn hint(grid: &Grid) -> Option<Hint>
+{
+    let solver = Solver::from_grid(grid);
+    if let Some(step) = solver.next_step()
+    {
+        let (pos, kind) = match step.safe.first()
+        {
+            Some(pos) => (*pos, HintKind::Safe),
+            None => (*step.mines.first()?, HintKind::Mine),
+        };
+        return Some(Hint
+        {
+            pos,
+            kind,
+            explanation: step.explanation,
+        });
+    }
+
+    let (pos, probability) = solver.unknown_probabilities()
+        .into_iter()
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))?;
+    Some(Hint
+    {
+        pos,
+        kind: HintKind::Guess(probability),
+        explanation: format!("nothing is certain, ({}, {}) has a {:.0}% chance of a mine", pos.x, pos.y, probability * 100.0),
+    })
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn test_hint()
+    {
+        let mut grid = Grid::new(3, 1);
+        grid.place_mine(Point::new(0, 0));
+        let guess = hint(&grid).unwrap();
+        assert!(matches!(guess.kind, HintKind::Guess(_)));
+
+        grid.reveal(Point::new(2, 0));
+        let certain = hint(&grid).unwrap();
+        assert_eq!(certain.pos, Point::new(0, 0));
+        assert_eq!(certain.kind, HintKind::Mine);
+    }
+}
@@ -1,5 +1,11 @@
 
+pub mod defuse;
+pub mod difficulty;
 pub mod grid;
+pub mod hint;
+pub mod session;
+pub mod solver;
+pub mod stats;
 
 pub fn hello_sim()
 {
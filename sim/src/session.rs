@@ -0,0 +1,326 @@
+
+use crate::defuse;
+use crate::defuse::DefuseOutcome;
+use crate::defuse::DefuseRules;
+use crate::defuse::Resources;
+use crate::difficulty;
+use crate::grid::Grid;
+use crate::grid::Reveal;
+use crate::hint;
+use crate::hint::Hint;
+
+use base::extents::Point;
+
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum SessionState
+{
+    #[default]
+    Playing,
+    Won,
+    Lost,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Summary
+{
+    pub won: bool,
+    pub three_bv: u32,
+    pub seconds: f32,
+    pub clicks: u32,
+    pub effective_clicks: u32,
+    pub three_bv_per_second: f32,
+    // share of the clicks that changed the board
+    pub click_efficiency: f32,
+    // index of efficiency, 3BV per click
+    pub ioe: f32,
+    pub hints: u32,
+    pub score: u32,
+}
+
+impl Display for Summary
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result
+    {
+        write!
+        (
+            f,
+            "{} -- 3BV {} in {:.2}s, 3BV/s {:.2}, clicks {} ({} effective), efficiency {:.0}%, IOE {:.2}",
+            if self.won { "Cleared" } else { "Lost" },
+            self.three_bv,
+            self.seconds,
+            self.three_bv_per_second,
+            self.clicks,
+            self.effective_clicks,
+            self.click_efficiency * 100.0,
+            self.ioe,
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ScoreRules
+{
+    pub per_three_bv: u32,
+    pub hint_penalty: u32,
+}
+
+impl Default for ScoreRules
+{
+    fn default() -> Self
+    {
+        Self
+        {
+            per_three_bv: 10,
+            hint_penalty: 25,
+        }
+    }
+}
+
+// tracks the player's clicks over a single game
+#[derive(Debug, Default, Clone)]
+pub struct Session
+{
+    state: SessionState,
+    // the clock only runs from the first move on
+    started: bool,
+    three_bv: u32,
+    seconds: f32,
+    clicks: u32,
+    effective_clicks: u32,
+    hints: u32,
+    score_rules: ScoreRules,
+}
+
+impl Session
+{
+    pub fn new(grid: &Grid) -> Self
+    {
+        Self
+        {
+            three_bv: difficulty::three_bv(grid),
+            ..Default::default()
+        }
+    }
+
+    pub fn state(&self) -> SessionState
+    {
+        self.state
+    }
+
+    pub fn is_over(&self) -> bool
+    {
+        self.state != SessionState::Playing
+    }
+
+    pub fn tick(&mut self, delta_seconds: f32)
+    {
+        if self.started && !self.is_over()
+        {
+            self.seconds += delta_seconds;
+        }
+    }
+
+    pub fn reveal(&mut self, grid: &mut Grid, pos: Point) -> Reveal
+    {
+        if self.is_over()
+        {
+            return Reveal::Nothing;
+        }
+
+        let reveal = grid.reveal(pos);
+        self.started = true;
+        self.clicks += 1;
+        match reveal
+        {
+            Reveal::Nothing => {},
+            Reveal::Safe(_) => self.effective_clicks += 1,
+            Reveal::Mine => self.state = SessionState::Lost,
+        }
+        self.update_cleared(grid);
+        reveal
+    }
+
+    pub fn defuse(&mut self, grid: &mut Grid, pos: Point, rules: &DefuseRules, resources: &mut Resources) -> Result<DefuseOutcome, defuse::Error>
+    {
+        if self.is_over()
+        {
+            return Err(defuse::Error::GameOver);
+        }
+
+        self.started = true;
+        self.clicks += 1;
+        let outcome = defuse::defuse(grid, pos, rules, resources)?;
+        if outcome == DefuseOutcome::Defused
+        {
+            self.effective_clicks += 1;
+        }
+        self.update_cleared(grid);
+        Ok(outcome)
+    }
+
+    // every hint handed out costs score at the end of the game
+    pub fn hint(&mut self, grid: &Grid) -> Option<Hint>
+    {
+        if self.is_over()
+        {
+            return None;
+        }
+
+        let hint = hint::hint(grid)?;
+        self.hints += 1;
+        Some(hint)
+    }
+
+    fn update_cleared(&mut self, grid: &Grid)
+    {
+        if self.state == SessionState::Playing && grid.is_cleared()
+        {
+            self.state = SessionState::Won;
+        }
+    }
+
+    pub fn summary(&self) -> Summary
+    {
+        let per_click = |value: u32| value as f32 / self.clicks.max(1) as f32;
+        // an instant game has no meaningful rate
+        let three_bv_per_second = if self.seconds > 0.0 { self.three_bv as f32 / self.seconds } else { 0.0 };
+        let score = if self.state == SessionState::Won
+        {
+            (self.three_bv * self.score_rules.per_three_bv).saturating_sub(self.hints * self.score_rules.hint_penalty)
+        }
+        else
+        {
+            0
+        };
+        Summary
+        {
+            won: self.state == SessionState::Won,
+            three_bv: self.three_bv,
+            seconds: self.seconds,
+            clicks: self.clicks,
+            effective_clicks: self.effective_clicks,
+            three_bv_per_second,
+            click_efficiency: per_click(self.effective_clicks),
+            ioe: per_click(self.three_bv),
+            hints: self.hints,
+            score,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    // one opening covering the board, the three cells around the mine in the corner border it
+    fn board() -> Grid
+    {
+        let mut grid = Grid::new(3, 3);
+        grid.place_mine(Point::new(0, 0));
+        grid
+    }
+
+    #[test]
+    fn test_tick()
+    {
+        let mut grid = board();
+        let mut session = Session::new(&grid);
+
+        session.tick(5.0);
+        assert_eq!(session.summary().seconds, 0.0);
+        assert_eq!(session.summary().three_bv_per_second, 0.0);
+
+        session.reveal(&mut grid, Point::new(1, 1));
+        session.tick(2.0);
+        session.reveal(&mut grid, Point::new(2, 2));
+        assert_eq!(session.state(), SessionState::Won);
+        session.tick(1.0);
+        assert_eq!(session.summary().seconds, 2.0);
+    }
+
+    #[test]
+    fn test_reveal_counting()
+    {
+        let mut grid = board();
+        let mut session = Session::new(&grid);
+
+        assert_eq!(session.reveal(&mut grid, Point::new(1, 1)), Reveal::Safe(1));
+        assert_eq!(session.reveal(&mut grid, Point::new(1, 1)), Reveal::Nothing);
+        assert!(matches!(session.reveal(&mut grid, Point::new(2, 2)), Reveal::Safe(_)));
+        assert_eq!(session.reveal(&mut grid, Point::new(0, 0)), Reveal::Nothing);
+
+        session.tick(2.0);
+        let summary = session.summary();
+        assert!(summary.won);
+        assert_eq!(summary.three_bv, 1);
+        assert_eq!(summary.clicks, 3);
+        assert_eq!(summary.effective_clicks, 2);
+        assert_eq!(summary.click_efficiency, 2.0 / 3.0);
+        assert_eq!(summary.ioe, 1.0 / 3.0);
+        assert_eq!(summary.score, ScoreRules::default().per_three_bv);
+    }
+
+    #[test]
+    fn test_defuse_counting()
+    {
+        let mut grid = board();
+        let mut session = Session::new(&grid);
+        let rules = DefuseRules::default();
+        let mut resources = Resources::new(&rules);
+
+        assert_eq!(session.defuse(&mut grid, Point::new(2, 2), &rules, &mut resources), Ok(DefuseOutcome::Wasted));
+        assert_eq!(session.defuse(&mut grid, Point::new(0, 0), &rules, &mut resources), Ok(DefuseOutcome::Defused));
+        assert!(session.defuse(&mut grid, Point::new(0, 0), &rules, &mut resources).is_err());
+
+        let summary = session.summary();
+        assert_eq!(summary.clicks, 3);
+        assert_eq!(summary.effective_clicks, 1);
+        assert!(!summary.won);
+        assert_eq!(summary.score, 0);
+    }
+
+    #[test]
+    fn test_hint_penalty()
+    {
+        let mut grid = board();
+        let rules = ScoreRules{ per_three_bv: 100, hint_penalty: 25 };
+        let mut session = Session{ score_rules: rules, ..Session::new(&grid) };
+
+        assert!(session.hint(&grid).is_some());
+        session.reveal(&mut grid, Point::new(2, 2));
+        assert_eq!(session.state(), SessionState::Won);
+        assert!(session.hint(&grid).is_none());
+
+        let summary = session.summary();
+        assert_eq!(summary.hints, 1);
+        assert_eq!(summary.score, rules.per_three_bv - rules.hint_penalty);
+
+        // the penalty never takes the score below zero
+        let mut grid = board();
+        let mut session = Session::new(&grid);
+        session.hint(&grid);
+        session.reveal(&mut grid, Point::new(2, 2));
+        assert_eq!(session.summary().score, 0);
+    }
+
+    #[test]
+    fn test_lost_summary()
+    {
+        let mut grid = board();
+        let mut session = Session::new(&grid);
+
+        assert_eq!(session.reveal(&mut grid, Point::new(0, 0)), Reveal::Mine);
+        assert_eq!(session.state(), SessionState::Lost);
+        assert_eq!(session.reveal(&mut grid, Point::new(2, 2)), Reveal::Nothing);
+
+        let summary = session.summary();
+        assert!(!summary.won);
+        assert_eq!(summary.clicks, 1);
+        assert_eq!(summary.ioe, 1.0);
+        assert_eq!(summary.score, 0);
+    }
+}
@@ -0,0 +1,349 @@
+
+use crate::grid::CellState;
+use crate::grid::Grid;
+
+use base::array2::Array2;
+use base::extents::Neighbours;
+use base::extents::Point;
+
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Knowledge
+{
+    #[default]
+    Unknown,
+    Safe(u8),
+    Mine,
+    // non playable cells never hold mines and never give information
+    Excluded,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Technique
+{
+    // a single number is satisfied by its unknown neighbours
+    Trivial,
+    // one number's unknown neighbours are a subset of another's
+    Subset,
+    // the total mine count decides the remaining cells
+    GlobalCount,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Step
+{
+    pub technique: Technique,
+    pub safe: Vec<Point>,
+    pub mines: Vec<Point>,
+    pub explanation: String,
+}
+
+#[derive(Debug, Clone)]
+struct Constraint
+{
+    source: Point,
+    unknown: Vec<Point>,
+    mines: u8,
+}
+
+// what the player can logically derive from the revealed part of a board
+#[derive(Debug, Clone)]
+pub struct Solver
+{
+    knowledge: Array2<Knowledge>,
+    mine_count: u32,
+}
+
+impl Solver
+{
+    pub fn from_grid(grid: &Grid) -> Self
+    {
+        let mut knowledge = Array2::<Knowledge>::from_size(grid.size());
+        let mut mine_count = 0;
+        for (pos, state) in grid.states.enumerate()
+        {
+            if state.contains(CellState::Mine)
+            {
+                mine_count += 1;
+            }
+
+            knowledge[pos] = if state.contains(CellState::NonPlayable)
+            {
+                Knowledge::Excluded
+            }
+            else if state.contains(CellState::Revealed | CellState::Mine)
+            {
+                Knowledge::Mine
+            }
+            else if state.contains(CellState::Revealed)
+            {
+                Knowledge::Safe(grid.adjacency[pos])
+            }
+            else
+            {
+                Knowledge::Unknown
+            };
+        }
+
+        Self
+        {
+            knowledge,
+            mine_count,
+        }
+    }
+
+    pub fn knowledge(&self) -> &Array2<Knowledge>
+    {
+        &self.knowledge
+    }
+
+    pub fn mine_count(&self) -> u32
+    {
+        self.mine_count
+    }
+
+    pub fn unknown_count(&self) -> usize
+    {
+        self.knowledge.raw_iter().filter(|k| **k == Knowledge::Unknown).count()
+    }
+
+    pub fn mines_left(&self) -> u32
+    {
+        let found = self.knowledge.raw_iter().filter(|k| **k == Knowledge::Mine).count() as u32;
+        self.mine_count.saturating_sub(found)
+    }
+
+    pub fn is_solved(&self) -> bool
+    {
+        self.unknown_count() == 0
+    }
+
+    // reveals a safe cell, opening up its neighbours like the game does when the cell has no adjacent mines
+    pub fn reveal(&mut self, grid: &Grid, pos: Point)
+    {
+        let mut open = vec![pos];
+        while let Some(pos) = open.pop()
+        {
+            if self.knowledge.get_by_index2(pos) != Some(&Knowledge::Unknown)
+            {
+                continue;
+            }
+
+            let adj = grid.adjacency[pos];
+            self.knowledge[pos] = Knowledge::Safe(adj);
+            if adj == 0
+            {
+                open.extend(self.knowledge.size().neighbours::<{ Neighbours::All.bits() }>(pos));
+            }
+        }
+    }
+
+    pub fn mark_mine(&mut self, pos: Point)
+    {
+        if let Some(knowledge) = self.knowledge.get_by_index2_mut(pos)
+        {
+            *knowledge = Knowledge::Mine;
+        }
+    }
+
+    pub fn apply(&mut self, grid: &Grid, step: &Step)
+    {
+        for pos in &step.mines
+        {
+            self.mark_mine(*pos);
+        }
+        for pos in &step.safe
+        {
+            self.reveal(grid, *pos);
+        }
+    }
+
+    fn constraints(&self) -> Vec<Constraint>
+    {
+        let size = self.knowledge.size();
+        let mut constraints = vec![];
+        for (pos, knowledge) in self.knowledge.enumerate()
+        {
+            let Knowledge::Safe(adj) = *knowledge else
+            {
+                continue;
+            };
+
+            let mut unknown = vec![];
+            let mut found = 0;
+            for neighbour_pos in size.neighbours::<{ Neighbours::All.bits() }>(pos)
+            {
+                match self.knowledge[neighbour_pos]
+                {
+                    Knowledge::Unknown => unknown.push(neighbour_pos),
+                    Knowledge::Mine => found += 1,
+                    _ => {},
+                }
+            }
+
+            if unknown.is_empty()
+            {
+                continue;
+            }
+            constraints.push(Constraint
+            {
+                source: pos,
+                unknown,
+                mines: adj.saturating_sub(found),
+            });
+        }
+        constraints
+    }
+
+    // rough chance of a mine for every unknown cell: the most pessimistic number touching it,
+    // or the density of the mines left for cells no number touches
+    pub fn unknown_probabilities(&self) -> Vec<(Point, f32)>
+    {
+        let mut probability = Array2::<Option<f32>>::from_size(self.knowledge.size());
+        for constraint in self.constraints()
+        {
+            let local = constraint.mines as f32 / constraint.unknown.len() as f32;
+            for pos in constraint.unknown
+            {
+                let cell = &mut probability[pos];
+                *cell = Some(cell.map_or(local, |p| p.max(local)));
+            }
+        }
+
+        let density = self.mines_left() as f32 / self.unknown_count().max(1) as f32;
+        self.knowledge.enumerate()
+            .filter(|(_, k)| **k == Knowledge::Unknown)
+            .map(|(pos, _)| (pos, probability[pos].unwrap_or(density)))
+            .collect()
+    }
+
+    // the next certain deduction, cheapest technique first
+    pub fn next_step(&self) -> Option<Step>
+    {
+        let constraints = self.constraints();
+        Self::trivial_step(&constraints)
+            .or_else(|| Self::subset_step(&constraints))
+            .or_else(|| self.global_step())
+    }
+
+    fn trivial_step(constraints: &[Constraint]) -> Option<Step>
+    {
+        for constraint in constraints
+        {
+            let unknown = constraint.unknown.len();
+            let (x, y) = (constraint.source.x, constraint.source.y);
+            if constraint.mines == 0
+            {
+                return Some(Step
+                {
+                    technique: Technique::Trivial,
+                    safe: constraint.unknown.clone(),
+                    mines: vec![],
+                    explanation: format!("({x}, {y}) has all of its mines found, {unknown} neighbours unknown are safe"),
+                });
+            }
+            if constraint.mines as usize == unknown
+            {
+                return Some(Step
+                {
+                    technique: Technique::Trivial,
+                    safe: vec![],
+                    mines: constraint.unknown.clone(),
+                    explanation: format!("({x}, {y}) has {unknown} neighbours unknown, number is {}", constraint.mines),
+                });
+            }
+        }
+        None
+    }
+
+    fn subset_step(constraints: &[Constraint]) -> Option<Step>
+    {
+        for a in constraints
+        {
+            for b in constraints
+            {
+                // only numbers within two cells of each other can share neighbours
+                let delta = (a.source - b.source).abs();
+                if a.source == b.source || delta.x > 2 || delta.y > 2
+                {
+                    continue;
+                }
+                if !a.unknown.iter().all(|pos| b.unknown.contains(pos))
+                {
+                    continue;
+                }
+
+                let rest: Vec<Point> = b.unknown.iter().copied().filter(|pos| !a.unknown.contains(pos)).collect();
+                if rest.is_empty() || b.mines < a.mines
+                {
+                    continue;
+                }
+
+                let rest_mines = (b.mines - a.mines) as usize;
+                let explanation = format!
+                (
+                    "({}, {}) needs {} mines, ({}, {}) needs {}, so the {} other cells of ({}, {}) hold {}",
+                    a.source.x, a.source.y, a.mines,
+                    b.source.x, b.source.y, b.mines,
+                    rest.len(), b.source.x, b.source.y, rest_mines,
+                );
+                if rest_mines == 0
+                {
+                    return Some(Step
+                    {
+                        technique: Technique::Subset,
+                        safe: rest,
+                        mines: vec![],
+                        explanation,
+                    });
+                }
+                if rest_mines == rest.len()
+                {
+                    return Some(Step
+                    {
+                        technique: Technique::Subset,
+                        safe: vec![],
+                        mines: rest,
+                        explanation,
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    fn global_step(&self) -> Option<Step>
+    {
+        let unknown: Vec<Point> = self.knowledge.enumerate()
+            .filter(|(_, k)| **k == Knowledge::Unknown)
+            .map(|(pos, _)| pos)
+            .collect();
+        if unknown.is_empty()
+        {
+            return None;
+        }
+
+        let left = self.mines_left() as usize;
+        let explanation = format!("{left} mines left among {} unknown cells", unknown.len());
+        if left == 0
+        {
+            return Some(Step
+            {
+                technique: Technique::GlobalCount,
+                safe: unknown,
+                mines: vec![],
+                explanation,
+            });
+        }
+        if left == unknown.len()
+        {
+            return Some(Step
+            {
+                technique: Technique::GlobalCount,
+                safe: vec![],
+                mines: unknown,
+                explanation,
+            });
+        }
+        None
+    }
+}
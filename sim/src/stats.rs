@@ -0,0 +1,211 @@
+
+use crate::grid::Grid;
+use crate::session::Summary;
+
+use base::migrate::Migration;
+use base::migrate::Versioned;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, serde::Deserialize, serde::Serialize)]
+pub struct BoardKey
+{
+    pub width: i32,
+    pub height: i32,
+    // mines per playable cell, in whole percent
+    pub density: u32,
+}
+
+impl BoardKey
+{
+    pub fn from_grid(grid: &Grid) -> Self
+    {
+        let size = grid.size();
+        let playable = grid.playable_count().max(1);
+        Self
+        {
+            width: size.width,
+            height: size.height,
+            density: (grid.mine_count() * 100 + playable / 2) / playable,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct PersonalBest
+{
+    pub board: BoardKey,
+    pub games_won: u32,
+    pub fastest_seconds: f32,
+    pub three_bv_per_second: f32,
+    pub ioe: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Stats
+{
+    pub version: u32,
+    pub games_played: u32,
+    pub bests: Vec<PersonalBest>,
+}
+
+impl Default for Stats
+{
+    fn default() -> Self
+    {
+        Self
+        {
+            version: Self::version(),
+            games_played: 0,
+            bests: vec![],
+        }
+    }
+}
+
+// version 1 named the best time of a board seconds, easily taken for the time of the last game
+fn rename_fastest_seconds(value: ron::Value) -> ron::Value
+{
+    let ron::Value::Map(mut map) = value else
+    {
+        return value;
+    };
+
+    let key = ron::Value::String("bests".into());
+    if let Some(ron::Value::Seq(bests)) = map.remove(&key)
+    {
+        let bests = bests.into_iter()
+            .map(|best| base::migrate::rename_field(best, "seconds", "fastest_seconds"))
+            .collect();
+        map.insert(key, ron::Value::Seq(bests));
+    }
+    ron::Value::Map(map)
+}
+
+impl Versioned for Stats
+{
+    fn migrations() -> &'static [Migration]
+    {
+        &[base::migrate::add_version, rename_fastest_seconds]
+    }
+}
+
+impl Stats
+{
+    pub fn path() -> &'static std::path::Path
+    {
+        std::path::Path::new("stats.ron")
+    }
+
+    // player data goes to the per-user config folder like user tuning, the assets folder ships with the game
+    pub fn folder() -> Option<std::path::PathBuf>
+    {
+        base::assets::user_config_dir()
+    }
+
+    // a missing file is a fresh player, not an error
+    pub fn load() -> std::io::Result<Self>
+    {
+        let Some(folder) = Self::folder() else
+        {
+            return Ok(Self::default());
+        };
+
+        match base::ronx::read_migrated_sync_in(&folder, Self::path(), Self::migrations())
+        {
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            result => result,
+        }
+    }
+
+    pub fn save(&self) -> std::io::Result<()>
+    {
+        let Some(folder) = Self::folder() else
+        {
+            return Err(std::io::Error::new(std::io::ErrorKind::NotFound, format!("No user config folder to write [{}] to", Self::path().display())));
+        };
+
+        base::ronx::write_sync_in(self, &folder, Self::path())
+    }
+
+    pub fn best(&self, board: &BoardKey) -> Option<&PersonalBest>
+    {
+        self.bests.iter().find(|best| best.board == *board)
+    }
+
+    // returns true when the game set a new best time for its board
+    pub fn record(&mut self, board: BoardKey, summary: &Summary) -> bool
+    {
+        self.games_played += 1;
+        if !summary.won
+        {
+            return false;
+        }
+
+        let Some(best) = self.bests.iter_mut().find(|best| best.board == board) else
+        {
+            self.bests.push(PersonalBest
+            {
+                board,
+                games_won: 1,
+                fastest_seconds: summary.seconds,
+                three_bv_per_second: summary.three_bv_per_second,
+                ioe: summary.ioe,
+            });
+            return true;
+        };
+
+        best.games_won += 1;
+        best.three_bv_per_second = best.three_bv_per_second.max(summary.three_bv_per_second);
+        best.ioe = best.ioe.max(summary.ioe);
+        if summary.seconds < best.fastest_seconds
+        {
+            best.fastest_seconds = summary.seconds;
+            return true;
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn migrated(text: &str) -> Stats
+    {
+        let value = ron::from_str(text).unwrap();
+        base::migrate::migrate(value, Stats::migrations()).unwrap().into_rust().unwrap()
+    }
+
+    #[test]
+    fn test_migrate_from_unversioned()
+    {
+        let stats = migrated(include_str!("../fixtures/stats_v0.ron"));
+        assert_eq!(stats.version, 2);
+        assert_eq!(stats.games_played, 3);
+        assert_eq!(stats.bests.len(), 1);
+        assert_eq!(stats.bests[0].board, BoardKey{ width: 5, height: 5, density: 14 });
+        assert_eq!(stats.bests[0].games_won, 2);
+        assert_eq!(stats.bests[0].fastest_seconds, 12.5);
+    }
+
+    #[test]
+    fn test_migrate_fastest_seconds()
+    {
+        let text = include_str!("../fixtures/stats_v1.ron");
+        // the field has no default, the old name alone can't be read by the current code
+        assert!(ron::from_str::<Stats>(text).is_err());
+
+        let stats = migrated(text);
+        assert_eq!(stats.version, 2);
+        assert_eq!(stats.bests.iter().map(|best| best.fastest_seconds).collect::<Vec<_>>(), vec![12.5, 40.25]);
+    }
+
+    #[test]
+    fn test_latest_round_trip()
+    {
+        let mut stats = migrated(include_str!("../fixtures/stats_v0.ron"));
+        stats.games_played += 1;
+        let text = ron::to_string(&stats).unwrap();
+        assert_eq!(migrated(&text), stats);
+        assert_eq!(Stats::default().version, Stats::version());
+    }
+}
@@ -1,5 +1,12 @@
 
 use bevy::prelude::*;
+use bevyx::ron::RonLoadErrors;
+use base::tuning::Tuning;
+use bevyx::tuning::TuningStatus;
+use vis::board_vis_tuning::BoardVisTuning;
+use vis::grid_vis::GridVis;
+use vis::grid_vis::PlayerStats;
+use sim::stats::Stats;
 use bevy::render::camera::RenderTarget;
 use bevy::window::PrimaryWindow;
 use bevy::window::WindowRef;
@@ -34,13 +41,18 @@ fn setup(mut commands: Commands)
     ));
 }
 
+#[allow(clippy::too_many_arguments)]
 fn primary_window_ui
 (
     keys: Res<ButtonInput<KeyCode>>,
     mut debug_state: ResMut<DebugState>,
     mut egui_ctx: Query<&mut EguiContext, With<PrimaryWindow>>,
     mut secondary_window: Query<&mut Window, Without<PrimaryWindow>>,
-    mut app_exit_events: ResMut<Events<bevy::app::AppExit>>
+    mut app_exit_events: ResMut<Events<bevy::app::AppExit>>,
+    mut board_vis_status: ResMut<TuningStatus<BoardVisTuning>>,
+    ron_load_errors: Res<RonLoadErrors>,
+    grid_vis: Res<GridVis>,
+    player_stats: Res<PlayerStats>,
 )
 {
     if keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) && keys.just_pressed(KeyCode::KeyC)
@@ -52,7 +64,15 @@ fn primary_window_ui
         debug_state.show_menu = !debug_state.show_menu;
     }
 
+    // broken tuning and stats and assets with errors or warnings don't wait for the menu to be opened,
+    // neither do the hint the player asked for and the summary of the finished game
     if !debug_state.show_menu
+        && board_vis_status.error.is_none()
+        && ron_load_errors.errors.is_empty()
+        && ron_load_errors.warnings.is_empty()
+        && player_stats.error().is_none()
+        && grid_vis.hint().is_none()
+        && grid_vis.summary().is_none()
     {
         return;
     }
@@ -70,7 +90,42 @@ fn primary_window_ui
                 {
                     secondary_window.single_mut().visible = !secondary_window.single().visible;
                 }
+                // the file is upgraded in memory on load, only this writes the upgrade back
+                if board_vis_status.outdated && ui.button(format!("Update {}", BoardVisTuning::path().display())).clicked()
+                {
+                    match BoardVisTuning::update_asset()
+                    {
+                        Ok(_) => board_vis_status.outdated = false,
+                        Err(err) => board_vis_status.error = Some(err),
+                    }
+                }
             });
+            // excerpts line carets up with the offending column, which needs a monospace font
+            let error_text = |text: &str| egui::RichText::new(text).monospace().color(egui::Color32::RED);
+            if let Some(err) = &board_vis_status.error
+            {
+                ui.label(error_text(&err.to_string()));
+            }
+            for (path, err) in &ron_load_errors.errors
+            {
+                ui.label(error_text(&format!("{path}\n{err}")));
+            }
+            for (path, warning) in &ron_load_errors.warnings
+            {
+                ui.label(egui::RichText::new(format!("{path}\n{warning}")).monospace().color(egui::Color32::YELLOW));
+            }
+            if let Some(err) = player_stats.error()
+            {
+                ui.label(error_text(&format!("{}\n{err}", Stats::path().display())));
+            }
+            if let Some(hint) = grid_vis.hint()
+            {
+                ui.label(format!("Hint -- {}", hint.explanation));
+            }
+            if let Some(summary) = grid_vis.summary()
+            {
+                ui.label(if grid_vis.new_best() { format!("{summary}, new personal best!") } else { summary.to_string() });
+            }
         });
 }
 
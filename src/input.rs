@@ -73,13 +73,28 @@ pub fn reveal_cell
 
     gizmos.circle_2d(point, 10., bevy::color::palettes::basic::WHITE);
 
-    if !mouse_buttons.just_pressed(MouseButton::Left)
+    if mouse_buttons.just_pressed(MouseButton::Left)
     {
-        return;
+        gizmos.circle_2d(point, 5., bevy::color::palettes::basic::RED);
+        grid_vis.on_tap(&board_vis_tuning, &point);
+    }
+    else if mouse_buttons.just_pressed(MouseButton::Middle)
+    {
+        gizmos.circle_2d(point, 5., bevy::color::palettes::basic::YELLOW);
+        grid_vis.on_defuse(&board_vis_tuning, &point);
     }
+}
 
-    gizmos.circle_2d(point, 5., bevy::color::palettes::basic::RED);
-    grid_vis.on_tap(&board_vis_tuning, &point);
+pub fn request_hint
+(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut grid_vis: ResMut<GridVis>,
+)
+{
+    if keys.just_pressed(KeyCode::KeyH)
+    {
+        grid_vis.on_hint();
+    }
 }
 
 pub fn camera_zoom
@@ -9,12 +9,13 @@ use bevy::render::RenderPlugin;
 use bevy::render::settings::Backends;
 use bevy::render::settings::WgpuSettings;
 use bevy_egui::EguiPlugin;
+use bevyx::loading::LoadingStatePlugin;
 
 #[derive(States, Debug, Clone, PartialEq, Eq, Hash)]
 enum AppState
 {
+    Loading,
     Frontend,
-    InGame,
 }
 
 fn find_assets_folder() -> Result<(), std::io::Error>
@@ -26,7 +27,6 @@ fn find_assets_folder() -> Result<(), std::io::Error>
         let assets_path = current_dir.join(base::assets::ASSETS_FOLDER);
         if assets_path.is_dir()
         {
-            std::env::set_current_dir(&current_dir)?;
             std::env::set_var(bevyx::helper::ASSET_ROOT_ENV, &current_dir);
             return Ok(());
         }
@@ -37,7 +37,7 @@ fn find_assets_folder() -> Result<(), std::io::Error>
         };
     }
 
-    Err(std::io::Error::new(std::io::ErrorKind::Other, "Could not find assets folder"))
+    Err(std::io::Error::other("Could not find assets folder"))
 }
 
 fn setup
@@ -47,7 +47,7 @@ fn setup
 {
     commands.spawn
     (
-        Camera2d::default()
+        Camera2d
     );
 }
 
@@ -56,7 +56,10 @@ fn main()
     let ext = base::extents::Extents{ width: 10, height: 10 };
     let _arr = ext.neighbours::<{ base::extents::Neighbours::Top.bits() }>(base::extents::Point::new(0, 0));
     let _arr = ext.neighbours::<{ base::extents::Neighbours::Top.union(base::extents::Neighbours::Bottom).bits() }>(base::extents::Point::new(0, 0));
-    let _ = find_assets_folder();
+    if let Err(err) = find_assets_folder()
+    {
+        eprintln!("{} -- {}", base::debug_name!(), err);
+    }
 
     base::hello_base();
     bevyx::hello_bevyx();
@@ -94,13 +97,18 @@ fn main()
                 ..default()
             },
         })
-        .insert_state(AppState::Frontend)
+        .insert_state(AppState::Loading)
+        .add_plugins(LoadingStatePlugin{ loading: AppState::Loading, next: AppState::Frontend })
         .add_plugins(crate::debug::DebugPlugin)
         .add_plugins(EguiPlugin)
-        .add_plugins(vis::GameVisPlugin)
+        .add_plugins(vis::GameVisPlugin{ loading: AppState::Loading })
         .add_systems(Startup, setup)
-        .add_systems(Update, input::camera_pan)
-        .add_systems(Update, input::camera_zoom)
-        .add_systems(Update, input::reveal_cell)
+        .add_systems(Update,
+        (
+            input::camera_pan,
+            input::camera_zoom,
+            input::reveal_cell,
+            input::request_hint,
+        ).run_if(not(in_state(AppState::Loading))))
         .run();
 }
@@ -1,9 +1,16 @@
 
+use base::migrate::Migration;
+use base::migrate::Versioned;
+use base::tuning::FieldError;
 use base::tuning::Tuning;
 use bevy::prelude::*;
+use bevyx::asset_ref::AssetRef;
+use bevyx::asset_ref::DependencyLoader;
+use bevyx::asset_ref::LoadDependencies;
 use bevy::reflect::TypePath;
 
 #[derive(Clone, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
 pub struct GridTuning
 {
     pub world_line_width: f32, // world space
@@ -22,19 +29,74 @@ impl Default for GridTuning
     }
 }
 
-#[derive(serde::Deserialize, serde::Serialize, Asset, Resource, TypePath)]
+#[derive(Clone, serde::Deserialize, serde::Serialize, Asset, Resource, TypePath)]
+#[serde(default)]
 pub struct BoardVisTuning 
 {
+    pub version: u32,
     pub cell_size: Vec2,
     pub grid: GridTuning,
-    pub adjacency_images: [std::path::PathBuf; 8],
+    pub adjacency_images: [AssetRef<Image>; 8],
+}
+
+impl LoadDependencies for BoardVisTuning
+{
+    fn load_dependencies(&mut self, loader: &mut impl DependencyLoader)
+    {
+        for image in &mut self.adjacency_images
+        {
+            image.load(loader);
+        }
+    }
+}
+
+impl Versioned for BoardVisTuning
+{
+    fn migrations() -> &'static [Migration]
+    {
+        &[base::migrate::add_version]
+    }
 }
 
 impl Tuning for BoardVisTuning
 {
     fn path() -> &'static std::path::Path
     {
-        &std::path::Path::new("tuning/board_vis.ron")
+        std::path::Path::new("tuning/board_vis.ron")
+    }
+
+    fn validate(&self) -> Result<(), Vec<FieldError>>
+    {
+        let mut errors = vec![];
+        if !(self.cell_size.x > 0.0 && self.cell_size.y > 0.0)
+        {
+            errors.push(FieldError::new("cell_size", "must be positive"));
+        }
+        if self.grid.world_line_width < 0.0
+        {
+            errors.push(FieldError::new("grid.world_line_width", "must not be negative"));
+        }
+        if !(0.0..=1.0).contains(&self.grid.uv_width)
+        {
+            errors.push(FieldError::new("grid.uv_width", "must be between 0 and 1"));
+        }
+        for (index, image) in self.adjacency_images.iter().enumerate()
+        {
+            if !base::assets::asset_exists(&image.path)
+            {
+                let message = format!("does not exist under {}/", base::assets::ASSETS_FOLDER);
+                errors.push(FieldError::new(format!("adjacency_images[{index}]"), message));
+            }
+        }
+
+        if errors.is_empty()
+        {
+            Ok(())
+        }
+        else
+        {
+            Err(errors)
+        }
     }
 }
 
@@ -44,18 +106,69 @@ impl Default for BoardVisTuning
     {
         Self
         {
+            version: <Self as Versioned>::version(),
             cell_size: Vec2::splat(28.0),
             grid: Default::default(),
             adjacency_images: [
-                "adjacency/1.png".into(),
-                "adjacency/2.png".into(),
-                "adjacency/3.png".into(),
-                "adjacency/4.png".into(),
-                "adjacency/5.png".into(),
-                "adjacency/6.png".into(),
-                "adjacency/7.png".into(),
-                "adjacency/8.png".into(),
+                AssetRef::new("adjacency/1.png"),
+                AssetRef::new("adjacency/2.png"),
+                AssetRef::new("adjacency/3.png"),
+                AssetRef::new("adjacency/4.png"),
+                AssetRef::new("adjacency/5.png"),
+                AssetRef::new("adjacency/6.png"),
+                AssetRef::new("adjacency/7.png"),
+                AssetRef::new("adjacency/8.png"),
             ],
         }
     }
 }
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn migrated(text: &str) -> BoardVisTuning
+    {
+        let value = ron::from_str(text).unwrap();
+        base::migrate::migrate(value, BoardVisTuning::migrations()).unwrap().into_rust().unwrap()
+    }
+
+    #[test]
+    fn test_migrate_from_unversioned()
+    {
+        // the fixture differs from the defaults, so its values can't come from serde filling in missing fields
+        let tuning = migrated(include_str!("../fixtures/board_vis_v0.ron"));
+        assert_eq!(tuning.version, 1);
+        assert_eq!(tuning.cell_size, Vec2::new(32.0, 24.0));
+        assert_eq!(tuning.grid.uv_width, 0.25);
+        assert_eq!(tuning.adjacency_images[7].path, std::path::PathBuf::from("adjacency/8.png"));
+    }
+
+    #[test]
+    fn test_validate()
+    {
+        let mut tuning = BoardVisTuning
+        {
+            cell_size: Vec2::new(28.0, 0.0),
+            ..Default::default()
+        };
+        tuning.grid.uv_width = 2.0;
+        tuning.adjacency_images[3] = AssetRef::new("adjacency/missing.png");
+
+        let fields = tuning.validate().unwrap_err().into_iter().map(|error| error.field).collect::<Vec<_>>();
+        assert!(fields.contains(&"cell_size".to_owned()));
+        assert!(fields.contains(&"grid.uv_width".to_owned()));
+        assert!(fields.contains(&"adjacency_images[3]".to_owned()));
+        assert!(!fields.contains(&"grid.world_line_width".to_owned()));
+    }
+
+    #[test]
+    fn test_latest_round_trip()
+    {
+        let tuning = BoardVisTuning::default();
+        assert_eq!(tuning.version, BoardVisTuning::version());
+        let text = ron::to_string(&tuning).unwrap();
+        assert_eq!(migrated(&text).version, tuning.version);
+    }
+}
@@ -1,7 +1,14 @@
 
 use crate::board_vis_tuning::*;
 use crate::layers;
+use crate::vis_assets::VisAssets;
+use base::debug_name;
+use bevyx::tuning::TuningChanged;
+use sim::defuse::*;
 use sim::grid::*;
+use sim::hint::*;
+use sim::session::*;
+use sim::stats::*;
 
 use bevy::prelude::*;
 use bevy::reflect::TypePath;
@@ -14,9 +21,6 @@ use bevy::render::mesh::MeshVertexBufferLayoutRef;
 
 use bitflags::bitflags;
 
-#[derive(Debug, Clone, Component)]
-struct EntityIndex2(IVec2);
-
 #[derive(Debug, Clone, Component)]
 struct EntityIndex(usize);
 
@@ -29,15 +33,19 @@ struct Cover;
 #[derive(Debug, Clone, Component)]
 struct Adjacency;
 
+#[derive(Debug, Clone, Component)]
+struct GridLines;
+
 bitflags!
 {
     #[repr(transparent)]
     #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
     struct CellDirty: u8
     {
-        const None = 0 << 0;
+        const None = 0;
         const Cover = 1 << 0;
         const Mine = 1 << 1;
+        const Adjacency = 1 << 2;
     }
 }
 
@@ -46,6 +54,12 @@ pub struct GridVis
 {
     dirty: CellDirty,
     grid: Grid,
+    defuse_rules: DefuseRules,
+    resources: Resources,
+    session: Session,
+    summary: Option<Summary>,
+    new_best: bool,
+    hint: Option<Hint>,
 }
 
 impl GridVis
@@ -54,12 +68,110 @@ impl GridVis
     {
         let pos = (world_pos / vis_tuning.cell_size).as_ivec2();
 
-        if let Some(cell) = self.grid.states.get_by_index2_mut(pos)
+        if self.session.reveal(&mut self.grid, pos) != Reveal::Nothing
         {
-            cell.insert(CellState::Revealed);
+            self.hint = None;
             self.dirty.insert(CellDirty::Cover | CellDirty::Mine);
         }
     }
+
+    pub fn on_defuse(&mut self, vis_tuning: &BoardVisTuning, world_pos: &Vec2)
+    {
+        let pos = (world_pos / vis_tuning.cell_size).as_ivec2();
+
+        match self.session.defuse(&mut self.grid, pos, &self.defuse_rules, &mut self.resources)
+        {
+            Ok(_) =>
+            {
+                self.hint = None;
+                self.dirty.insert(CellDirty::Cover | CellDirty::Mine | CellDirty::Adjacency);
+            },
+            Err(err) =>
+            {
+                eprintln!("{} -- {}", debug_name!(), err);
+            }
+        }
+    }
+
+    // the hint is circled on the board until the next move, the debug panel shows its explanation
+    pub fn on_hint(&mut self)
+    {
+        self.hint = self.session.hint(&self.grid);
+    }
+
+    pub fn hint(&self) -> Option<&Hint>
+    {
+        self.hint.as_ref()
+    }
+
+    pub fn resources(&self) -> &Resources
+    {
+        &self.resources
+    }
+
+    pub fn summary(&self) -> Option<&Summary>
+    {
+        self.summary.as_ref()
+    }
+
+    // whether the finished game set a new best time for its board
+    pub fn new_best(&self) -> bool
+    {
+        self.new_best
+    }
+}
+
+// read once when the plugin is built, every finished game is recorded into it and written back
+#[derive(Debug, Resource)]
+pub struct PlayerStats
+{
+    stats: Stats,
+    // a file that failed to load is left alone rather than overwritten with fresh stats
+    writable: bool,
+    error: Option<std::io::Error>,
+}
+
+impl PlayerStats
+{
+    pub fn load() -> Self
+    {
+        match Stats::load()
+        {
+            Ok(stats) => Self{ stats, writable: true, error: None },
+            Err(err) =>
+            {
+                eprintln!("{} -- Failed to load [{}] with error [{}]", debug_name!(), Stats::path().display(), err);
+                Self{ stats: Stats::default(), writable: false, error: Some(err) }
+            }
+        }
+    }
+
+    pub fn stats(&self) -> &Stats
+    {
+        &self.stats
+    }
+
+    pub fn error(&self) -> Option<&std::io::Error>
+    {
+        self.error.as_ref()
+    }
+
+    // returns true when the game set a new best time for its board
+    fn record(&mut self, board: BoardKey, summary: &Summary) -> bool
+    {
+        let new_best = self.stats.record(board, summary);
+        if !self.writable
+        {
+            return new_best;
+        }
+
+        self.error = self.stats.save().err();
+        if let Some(err) = &self.error
+        {
+            eprintln!("{} -- Failed to write [{}] with error [{}]", debug_name!(), Stats::path().display(), err);
+        }
+        new_best
+    }
 }
 
 #[derive(Asset, TypePath, AsBindGroup, Debug, Clone)]
@@ -106,16 +218,11 @@ impl PartialEq for GridVert
     {
         self.pos.eq(&other.pos)
     }
-
-    fn ne(&self, other: &Self) -> bool
-    {
-        self.pos.ne(&other.pos)
-    }
 }
 
 impl GridVert
 {
-    fn from_pos(&self, pos: Vec2) -> Self
+    fn with_pos(&self, pos: Vec2) -> Self
     {
         Self
         {
@@ -176,7 +283,7 @@ fn spawn_grid
     (
         GridMaterial
         {
-            color: LinearRgba::BLACK.into(),
+            color: LinearRgba::BLACK,
         }
     );
 
@@ -219,10 +326,10 @@ fn spawn_grid
         for x in 0..=size.width
         {
             let cell_intersection = Vec2::new(x as f32, y as f32);
-            let tli = geo.insert_vert(tl.from_pos(cell_intersection * vis_tuning.cell_size + tl.pos * corner_size));
-            let tri = geo.insert_vert(tr.from_pos(cell_intersection * vis_tuning.cell_size + tr.pos * corner_size));
-            let bli = geo.insert_vert(bl.from_pos(cell_intersection * vis_tuning.cell_size + bl.pos * corner_size));
-            let bri = geo.insert_vert(br.from_pos(cell_intersection * vis_tuning.cell_size + br.pos * corner_size));
+            let tli = geo.insert_vert(tl.with_pos(cell_intersection * vis_tuning.cell_size + tl.pos * corner_size));
+            let tri = geo.insert_vert(tr.with_pos(cell_intersection * vis_tuning.cell_size + tr.pos * corner_size));
+            let bli = geo.insert_vert(bl.with_pos(cell_intersection * vis_tuning.cell_size + bl.pos * corner_size));
+            let bri = geo.insert_vert(br.with_pos(cell_intersection * vis_tuning.cell_size + br.pos * corner_size));
 
             geo.insert_index(tli);
             geo.insert_index(tri);
@@ -241,10 +348,10 @@ fn spawn_grid
         {
             let cell_intersection = Vec2::new(x as f32, y as f32);
             let next_cell_intersection = Vec2::new((x + 1) as f32, y as f32);
-            let tli = geo.insert_vert(tr.from_pos(cell_intersection * vis_tuning.cell_size + tr.pos * corner_size));
-            let tri = geo.insert_vert(tl.from_pos(next_cell_intersection * vis_tuning.cell_size + tl.pos * corner_size));
-            let bli = geo.insert_vert(br.from_pos(cell_intersection * vis_tuning.cell_size + br.pos * corner_size));
-            let bri = geo.insert_vert(bl.from_pos(next_cell_intersection * vis_tuning.cell_size + bl.pos * corner_size));
+            let tli = geo.insert_vert(tr.with_pos(cell_intersection * vis_tuning.cell_size + tr.pos * corner_size));
+            let tri = geo.insert_vert(tl.with_pos(next_cell_intersection * vis_tuning.cell_size + tl.pos * corner_size));
+            let bli = geo.insert_vert(br.with_pos(cell_intersection * vis_tuning.cell_size + br.pos * corner_size));
+            let bri = geo.insert_vert(bl.with_pos(next_cell_intersection * vis_tuning.cell_size + bl.pos * corner_size));
 
             geo.insert_index(tli);
             geo.insert_index(tri);
@@ -263,10 +370,10 @@ fn spawn_grid
         {
             let cell_intersection = Vec2::new(x as f32, y as f32);
             let next_cell_intersection = Vec2::new(x as f32, (y + 1) as f32);
-            let tli = geo.insert_vert(bl.from_pos(cell_intersection * vis_tuning.cell_size + bl.pos * corner_size));
-            let tri = geo.insert_vert(br.from_pos(cell_intersection * vis_tuning.cell_size + br.pos * corner_size));
-            let bli = geo.insert_vert(tl.from_pos(next_cell_intersection * vis_tuning.cell_size + tl.pos * corner_size));
-            let bri = geo.insert_vert(tr.from_pos(next_cell_intersection * vis_tuning.cell_size + tr.pos * corner_size));
+            let tli = geo.insert_vert(bl.with_pos(cell_intersection * vis_tuning.cell_size + bl.pos * corner_size));
+            let tri = geo.insert_vert(br.with_pos(cell_intersection * vis_tuning.cell_size + br.pos * corner_size));
+            let bli = geo.insert_vert(tl.with_pos(next_cell_intersection * vis_tuning.cell_size + tl.pos * corner_size));
+            let bri = geo.insert_vert(tr.with_pos(next_cell_intersection * vis_tuning.cell_size + tr.pos * corner_size));
 
             geo.insert_index(tli);
             geo.insert_index(tri);
@@ -308,24 +415,23 @@ fn spawn_grid
     commands
         .spawn
         ((
-            Mesh2d(mesh_id.into()),
-            MeshMaterial2d(custom_material.into())
+            GridLines,
+            Mesh2d(mesh_id),
+            MeshMaterial2d(custom_material)
         ));
 }
 
 fn spawn_mines
 (
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
+    vis_assets: Res<VisAssets>,
     vis_tuning: Res<BoardVisTuning>,
     grid_vis: Res<GridVis>,
 )
 {
-    let image = asset_server.load("textures/mine.png");
-
     let mine = Sprite
     {
-        image,
+        image: vis_assets.mine.clone(),
         custom_size: Some(vis_tuning.cell_size),
         anchor: Anchor::BottomLeft,
         ..default()
@@ -342,7 +448,6 @@ fn spawn_mines
         ((
             Mine,
             EntityIndex(grid_vis.grid.states.get_index(index2).unwrap()),
-            EntityIndex2(index2),
             mine.clone(),
             Transform::from_translation(world_pos.extend(layers::MINE))
         ));
@@ -352,16 +457,14 @@ fn spawn_mines
 fn spawn_covers
 (
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
+    vis_assets: Res<VisAssets>,
     vis_tuning: Res<BoardVisTuning>,
     grid_vis: Res<GridVis>,
 )
 {
-    let image = asset_server.load("textures/cover.png");
-
     let cover = Sprite
     {
-        image,
+        image: vis_assets.cover.clone(),
         custom_size: Some(vis_tuning.cell_size),
         anchor: Anchor::BottomLeft,
         ..default()
@@ -378,7 +481,6 @@ fn spawn_covers
         ((
             Cover,
             EntityIndex(grid_vis.grid.states.get_index(index2).unwrap()),
-            EntityIndex2(index2),
             cover.clone(),
             Transform::from_translation(world_pos.extend(layers::COVER))
         ));
@@ -400,8 +502,7 @@ fn reveal_covers
     {
         let Some(state) = grid_vis.grid.states.get_by_index(index.0) else
         {
-            assert!(false, "covers should always be kept up to date with the size of the grid");
-            continue;
+            unreachable!("covers should always be kept up to date with the size of the grid");
         };
 
         *visibility = if state.contains(CellState::Revealed)
@@ -415,19 +516,130 @@ fn reveal_covers
     }
 }
 
-fn spawn_adjacency
+fn remove_defused_mines
 (
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
+    mine_query: Query<(Entity, &EntityIndex), With<Mine>>,
+    grid_vis: Res<GridVis>,
+)
+{
+    if !grid_vis.dirty.contains(CellDirty::Mine)
+    {
+        return;
+    }
+
+    for (entity, index) in &mine_query
+    {
+        let Some(state) = grid_vis.grid.states.get_by_index(index.0) else
+        {
+            unreachable!("mines should always be kept up to date with the size of the grid");
+        };
+
+        if !state.contains(CellState::Mine)
+        {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn respawn_adjacency
+(
+    mut commands: Commands,
+    adjacency_query: Query<Entity, With<Adjacency>>,
+    vis_tuning: Res<BoardVisTuning>,
+    grid_vis: Res<GridVis>,
+)
+{
+    if !grid_vis.dirty.contains(CellDirty::Adjacency)
+    {
+        return;
+    }
+
+    for entity in &adjacency_query
+    {
+        commands.entity(entity).despawn();
+    }
+    spawn_adjacency(commands, vis_tuning, grid_vis);
+}
+
+type BoardFilter = Or<(With<GridLines>, With<Mine>, With<Cover>, With<Adjacency>)>;
+
+// the board entities are sized from the tuning, a reload tears them down so they get spawned again
+fn despawn_board
+(
+    mut commands: Commands,
+    board_query: Query<Entity, BoardFilter>,
+)
+{
+    for entity in &board_query
+    {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn tick_session
+(
+    time: Res<Time>,
+    mut grid_vis: ResMut<GridVis>,
+)
+{
+    grid_vis.session.tick(time.delta_secs());
+}
+
+// the debug panel shows the summary of the finished game
+fn finish_session
+(
+    mut grid_vis: ResMut<GridVis>,
+    mut player_stats: ResMut<PlayerStats>,
+)
+{
+    if !grid_vis.session.is_over() || grid_vis.summary.is_some()
+    {
+        return;
+    }
+
+    let summary = grid_vis.session.summary();
+    grid_vis.new_best = player_stats.record(BoardKey::from_grid(&grid_vis.grid), &summary);
+    grid_vis.summary = Some(summary);
+}
+
+fn draw_hint
+(
+    mut gizmos: Gizmos,
     vis_tuning: Res<BoardVisTuning>,
     grid_vis: Res<GridVis>,
 )
 {
-    let mut it = vis_tuning.adjacency_images
-        .iter()
-        .map( |path| asset_server.load(&**path) );
-    let images = std::array::from_fn::<Handle<Image>, 8, _>( |_| it.next().unwrap() );
+    let Some(hint) = &grid_vis.hint else
+    {
+        return;
+    };
 
+    let color = match hint.kind
+    {
+        HintKind::Safe => bevy::color::palettes::basic::LIME,
+        HintKind::Mine => bevy::color::palettes::basic::RED,
+        HintKind::Guess(_) => bevy::color::palettes::basic::YELLOW,
+    };
+    let center = (hint.pos.as_vec2() + 0.5) * vis_tuning.cell_size;
+    gizmos.circle_2d(center, vis_tuning.cell_size.min_element() * 0.4, color);
+}
+
+fn clear_dirty
+(
+    mut grid_vis: ResMut<GridVis>,
+)
+{
+    grid_vis.dirty = CellDirty::None;
+}
+
+fn spawn_adjacency
+(
+    mut commands: Commands,
+    vis_tuning: Res<BoardVisTuning>,
+    grid_vis: Res<GridVis>,
+)
+{
     for (index, (adj, state)) in grid_vis.grid.adjacency.raw_iter().zip(grid_vis.grid.states.raw_iter()).enumerate()
     {
         if state.intersects(CellState::NonPlayable | CellState::Mine) || *adj == 0
@@ -437,19 +649,17 @@ fn spawn_adjacency
 
         let adj_sprite = Sprite
         {
-            image: images[(adj - 1) as usize].clone(),
+            image: vis_tuning.adjacency_images[(adj - 1) as usize].handle.clone(),
             custom_size: Some(vis_tuning.cell_size),
             anchor: Anchor::BottomLeft,
             ..default()
         };
 
-        println!("adjacency");
         let index2 =  grid_vis.grid.states.get_index2(index).unwrap();
         let world_pos = index2.as_vec2() * vis_tuning.cell_size;
         commands.spawn
         ((
             Adjacency,
-            EntityIndex2(index2),
             EntityIndex(index),
             adj_sprite,
             Transform::from_translation(world_pos.extend(layers::ADJACENCY))
@@ -457,8 +667,13 @@ fn spawn_adjacency
     }
 }
 
-pub struct GridVisPlugin;
-impl Plugin for GridVisPlugin
+// the board is spawned once the loading state is left, its tuning and textures are all in by then
+pub struct GridVisPlugin<S>
+{
+    pub loading: S,
+}
+
+impl<S: States> Plugin for GridVisPlugin<S>
 {
     fn build(&self, app: &mut App)
     {
@@ -467,15 +682,41 @@ impl Plugin for GridVisPlugin
         *grid.states.get_by_index2_mut((1, 1).into()).unwrap() = CellState::Mine;
         *grid.states.get_by_index2_mut((4, 4).into()).unwrap() = CellState::Mine;
         grid.update_adjacency();
+        let session = Session::new(&grid);
+        let defuse_rules = DefuseRules::default();
 
         app
-            .insert_resource(GridVis{ dirty: CellDirty::None, grid })
+            .insert_resource(GridVis
+            {
+                dirty: CellDirty::None,
+                grid,
+                defuse_rules,
+                resources: Resources::new(&defuse_rules),
+                session,
+                summary: None,
+                new_best: false,
+                hint: None,
+            })
+            .insert_resource(PlayerStats::load())
             .add_plugins(Material2dPlugin::<GridMaterial>::default())
-            .add_systems(Startup, spawn_adjacency)
-            .add_systems(Startup, spawn_grid)
-            .add_systems(Startup, spawn_mines)
-            .add_systems(Startup, spawn_covers)
-            .add_systems(Update, reveal_covers)
+            .add_systems(OnExit(self.loading.clone()), (spawn_adjacency, spawn_grid, spawn_mines, spawn_covers))
+            // input mutates the grid during Update, consume the dirty flags after it
+            .add_systems(PostUpdate, reveal_covers)
+            .add_systems(PostUpdate, remove_defused_mines)
+            // the commands of the respawn are applied before a tuning rebuild, which then despawns them with the rest
+            .add_systems(PostUpdate, respawn_adjacency.before(despawn_board))
+            .add_systems(PostUpdate, finish_session)
+            .add_systems(PostUpdate, draw_hint)
+            .add_systems(PreUpdate, tick_session)
+            .add_systems
+            (
+                PostUpdate,
+                (despawn_board, spawn_grid, spawn_mines, spawn_covers, spawn_adjacency)
+                    .chain()
+                    .run_if(on_event::<TuningChanged<BoardVisTuning>>)
+                    .run_if(not(in_state(self.loading.clone())))
+            )
+            .add_systems(Last, clear_dirty)
             ;
     }
 }
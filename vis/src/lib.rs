@@ -3,10 +3,12 @@ pub mod board_vis_tuning;
 pub mod grid_vis;
 mod terrain_vis;
 mod layers;
+pub mod vis_assets;
 
-use base::tuning::Tuning;
-use bevyx::ron::RonAssetPlugin;
+use bevyx::loading::LoadingAppExt;
+use bevyx::tuning::TuningPlugin;
 use board_vis_tuning::*;
+use vis_assets::VisAssets;
 
 use bevy::prelude::*;
 
@@ -15,25 +17,20 @@ pub fn hello_vis()
     println!("Hello, vis!");
 }
 
-fn pre_startup
-(
-    mut commands: Commands,
-)
+// loading is the state waiting for the tuning and VisAssets, the board is spawned when it's left
+pub struct GameVisPlugin<S>
 {
-    BoardVisTuning::default().save();
-    commands.insert_resource(BoardVisTuning::load());
+    pub loading: S,
 }
 
-pub struct GameVisPlugin;
-
-impl Plugin for GameVisPlugin
+impl<S: States> Plugin for GameVisPlugin<S>
 {
     fn build(&self, app: &mut App)
     {
         app
-            .add_plugins(RonAssetPlugin::<BoardVisTuning>::default())
-            .add_plugins(terrain_vis::TerrainVisPlugin{})
-            .add_plugins(grid_vis::GridVisPlugin{})
-            .add_systems(PreStartup, pre_startup);
+            .add_plugins(TuningPlugin::<BoardVisTuning>::default())
+            .init_collection::<VisAssets>()
+            .add_plugins(terrain_vis::TerrainVisPlugin{ loading: self.loading.clone() })
+            .add_plugins(grid_vis::GridVisPlugin{ loading: self.loading.clone() });
     }
 }
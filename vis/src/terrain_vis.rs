@@ -3,6 +3,7 @@ use base::array2::*;
 use base::extents::*;
 use crate::board_vis_tuning::*;
 use crate::layers;
+use crate::vis_assets::VisAssets;
 
 use bevy::prelude::*;
 use bevy::reflect::TypePath;
@@ -26,7 +27,7 @@ fn blur<T, const N: usize>(data: &mut Array2<T>, kernel: &[T; N], passes: u32)
     for _ in 0..passes
     {
         // hori pass
-        for pos in data.index2_space()
+        for (pos, write) in temp.enumerate_mut()
         {
             let mut acc = T::default();
             for d in -delta..=delta
@@ -36,11 +37,11 @@ fn blur<T, const N: usize>(data: &mut Array2<T>, kernel: &[T; N], passes: u32)
                     acc += read * kernel[(delta + d) as usize];
                 }
             }
-            temp[pos] = acc;
+            *write = acc;
         }
 
         // vert pass
-        for pos in data.index2_space()
+        for (pos, write) in data.enumerate_mut()
         {
             let mut acc = T::default();
             for d in -delta..=delta
@@ -50,7 +51,7 @@ fn blur<T, const N: usize>(data: &mut Array2<T>, kernel: &[T; N], passes: u32)
                     acc += read * kernel[(delta + d) as usize];
                 }
             }
-            data[pos] = acc;
+            *write = acc;
         }
     }
 }
@@ -107,7 +108,7 @@ bitflags!
     #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
     pub struct CellType: u8
     {
-        const None = 0 << 0;
+        const None = 0;
         const Land = 1 << 0;
     }
 }
@@ -119,8 +120,8 @@ fn startup
     mut materials: ResMut<Assets<TerrainMaterial>>,
     mut images: ResMut<Assets<Image>>,
     vis_tuning: Res<BoardVisTuning>,
-    asset_server: Res<AssetServer>,
-    
+    vis_assets: Res<VisAssets>,
+
 )
 {
     let size = Extents::new(5, 5);
@@ -140,9 +141,9 @@ fn startup
             size.height * HEIGHT_MAP_SCALE
         );
 
-        for pos in height_map.index2_space()
+        for (pos, height) in height_map.enumerate_mut()
         {
-            height_map[pos] = if vis.cell_type[pos / HEIGHT_MAP_SCALE] == CellType::Land
+            *height = if vis.cell_type[pos / HEIGHT_MAP_SCALE] == CellType::Land
                 { 1.0 }
                 else
                 { 0.0 };
@@ -151,8 +152,7 @@ fn startup
         guassian_blur(&mut height_map, 2);
 
         let elevation_size = height_map.size();
-        let mut elevation: Vec<u8> = vec![];
-        elevation.resize(elevation_size.width as usize * elevation_size.height as usize * size_of::<u32>(), 0);
+        let mut elevation: Vec<u8> = vec![0; elevation_size.width as usize * elevation_size.height as usize * size_of::<u32>()];
         let elevation_slice = bytemuck::cast_slice_mut::<u8, u32>(&mut elevation);
         for i in 0..elevation_size.num_elements()
         {
@@ -189,7 +189,7 @@ fn startup
         {
             color: Color::WHITE.into(),
             elevation: elevation_handle,
-            color_palette: asset_server.load("textures/palette.png"),
+            color_palette: vis_assets.palette.clone(),
         }
     );
 
@@ -233,15 +233,18 @@ fn startup
     commands
         .spawn
         ((
-            Mesh2d(mesh_id.into()),
-            MeshMaterial2d(custom_material.into()),
+            Mesh2d(mesh_id),
+            MeshMaterial2d(custom_material),
             Transform::default(),
         ));
 }
 
-pub struct TerrainVisPlugin;
+pub struct TerrainVisPlugin<S>
+{
+    pub loading: S,
+}
 
-impl Plugin for TerrainVisPlugin
+impl<S: States> Plugin for TerrainVisPlugin<S>
 {
     fn build(&self, app: &mut App)
     {
@@ -251,6 +254,6 @@ impl Plugin for TerrainVisPlugin
                 cell_type: Array2::<CellType>::new(5, 5)
             })
             .add_plugins(Material2dPlugin::<TerrainMaterial>::default())
-            .add_systems(Startup, startup);
+            .add_systems(OnExit(self.loading.clone()), startup);
     }
 }
@@ -0,0 +1,41 @@
+use bevy::asset::UntypedHandle;
+use bevy::prelude::*;
+use bevyx::loading::AssetCollection;
+
+// the textures and shaders of the board, the game waits in the loading state until they are all in
+#[derive(Resource)]
+pub struct VisAssets
+{
+    pub mine: Handle<Image>,
+    pub cover: Handle<Image>,
+    pub palette: Handle<Image>,
+    pub grid_shader: Handle<Shader>,
+    pub terrain_shader: Handle<Shader>,
+}
+
+impl AssetCollection for VisAssets
+{
+    fn load(asset_server: &AssetServer) -> Self
+    {
+        Self
+        {
+            mine: asset_server.load("textures/mine.png"),
+            cover: asset_server.load("textures/cover.png"),
+            palette: asset_server.load("textures/palette.png"),
+            grid_shader: asset_server.load("shaders/grid.wgsl"),
+            terrain_shader: asset_server.load("shaders/terrain.wgsl"),
+        }
+    }
+
+    fn handles(&self) -> Vec<UntypedHandle>
+    {
+        vec!
+        [
+            self.mine.clone().untyped(),
+            self.cover.clone().untyped(),
+            self.palette.clone().untyped(),
+            self.grid_shader.clone().untyped(),
+            self.terrain_shader.clone().untyped(),
+        ]
+    }
+}